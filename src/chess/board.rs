@@ -1,11 +1,16 @@
 use crate::chess::bitboard::Bitboard;
 use crate::chess::board_state::BoardState;
-use crate::chess::consts::{MAX_MOVES, NUM_PIECES, NUM_SQUARES};
-use crate::chess::move_generator::{BLACK, WHITE};
+use crate::chess::consts::{NUM_PIECES, NUM_SQUARES};
+use crate::chess::move_generator::{
+    self, BLACK, BLACK_LONG_NOT_ATTACKED_SQUARES, BLACK_LONG_NOT_OCCUPIED_SQUARES,
+    BLACK_SHORT_NOT_ATTACKED_SQUARES, WHITE, WHITE_LONG_NOT_ATTACKED_SQUARES,
+    WHITE_LONG_NOT_OCCUPIED_SQUARES, WHITE_SHORT_NOT_ATTACKED_SQUARES,
+};
 use crate::chess::move_ply::MovePly;
 use crate::chess::types::color::Color;
 use crate::chess::types::file::File;
 use crate::chess::types::move_flag::MoveFlag;
+use crate::chess::types::piece::BasePiece::{Bishop, Knight, Pawn};
 use crate::chess::types::piece::Piece::{
     BlackBishop, BlackKing, BlackPawn, BlackQueen, BlackRook, NoPiece, WhiteBishop, WhiteKing,
     WhitePawn, WhiteQueen, WhiteRook,
@@ -15,8 +20,12 @@ use crate::chess::types::piece::{
 };
 use crate::chess::types::rank::Rank;
 use crate::chess::types::square::Square;
-use crate::precomputed::accessor::ZOBRIST;
+use crate::general::bits;
+use crate::precomputed::accessor::{
+    IN_BETWEEN, MOVEMENT_MASKS, ZOBRIST, bishop_lookup, rook_lookup, slider_lookup,
+};
 use std::fmt::Display;
+use std::sync::Arc;
 
 // if a piece on a certain square moves then the castling rights must change as well
 const SQUARE_MOVED_CASTLING: [u8; NUM_SQUARES] = [
@@ -27,7 +36,7 @@ const SQUARE_MOVED_CASTLING: [u8; NUM_SQUARES] = [
 const INCREMENT_ZOBRIST: bool = true;
 const IGNORE_ZOBRIST: bool = false;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Board {
     bitboards: [Bitboard; NUM_PIECES],
     piece_squares: [Piece; NUM_SQUARES],
@@ -43,10 +52,19 @@ pub struct Board {
     castling_rights: u8,
     half_move_clock: u8,
     zobrist: u64,
-    in_check: bool,
 
-    board_states: [BoardState; MAX_MOVES],
-    cur_board_state: usize,
+    // enemy pieces giving check to the side to move
+    checkers: u64,
+
+    // partial hashes of the board used to key the correction histories
+    pawn_zobrist: u64,
+    non_pawn_zobrist: [u64; 2],
+    minor_zobrist: u64,
+
+    // the game up to the search root, shared between clones since only the front-ends add to it
+    game_history: Arc<Vec<BoardState>>,
+    // what make_move has stacked on top of the root, so never deeper than a search goes
+    board_states: Vec<BoardState>,
 }
 
 impl Default for Board {
@@ -66,10 +84,15 @@ impl Default for Board {
             castling_rights: 0,
             half_move_clock: 0,
             zobrist: 0,
-            in_check: false,
 
-            board_states: [BoardState::default(); MAX_MOVES],
-            cur_board_state: 0,
+            checkers: 0,
+
+            pawn_zobrist: 0,
+            non_pawn_zobrist: [0; 2],
+            minor_zobrist: 0,
+
+            game_history: Arc::default(),
+            board_states: Vec::new(),
         };
 
         board
@@ -77,6 +100,101 @@ impl Default for Board {
 }
 
 impl Board {
+    /// A fresh board set up from `fen`, which is trusted to be valid.
+    pub fn from_fen(fen: &str) -> Board {
+        let mut board = Board::default();
+        board.new(fen);
+        board
+    }
+
+    /// Like `from_fen`, but for fens from outside the engine that may not describe a board at all.
+    pub fn try_from_fen(fen: &str) -> Result<Board, String> {
+        Self::validate_fen(fen)?;
+        Ok(Board::from_fen(fen))
+    }
+
+    // only checks what new relies on, not whether the position could come up in a game
+    fn validate_fen(fen: &str) -> Result<(), String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 3 || fields.len() > 6 {
+            return Err(format!("fen '{fen}' needs between 3 and 6 fields"));
+        }
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(format!("fen '{fen}' needs 8 ranks"));
+        }
+
+        let mut white_kings = 0;
+        let mut black_kings = 0;
+        for rank in ranks {
+            let mut num_squares = 0;
+
+            for char in rank.chars() {
+                match char {
+                    '1'..='8' => num_squares += char.to_digit(10).unwrap(),
+                    'K' => white_kings += 1,
+                    'k' => black_kings += 1,
+                    _ if char_to_piece(char).is_none() => {
+                        return Err(format!("fen '{fen}' has an unknown piece '{char}'"));
+                    }
+                    _ => {}
+                }
+
+                if !char.is_ascii_digit() {
+                    num_squares += 1;
+                }
+            }
+
+            if num_squares != 8 {
+                return Err(format!("fen '{fen}' has a rank without 8 squares"));
+            }
+        }
+
+        if white_kings != 1 || black_kings != 1 {
+            return Err(format!("fen '{fen}' needs exactly one king per side"));
+        }
+
+        if !matches!(fields[1], "w" | "b") {
+            return Err(format!("fen '{fen}' has side to move '{}'", fields[1]));
+        }
+
+        let castling = fields[2];
+        let valid_castling = castling == "-"
+            || (castling.chars().all(|char| "KQkq".contains(char))
+                && castling
+                    .chars()
+                    .enumerate()
+                    .all(|(i, char)| !castling[i + 1..].contains(char)));
+        if !valid_castling {
+            return Err(format!("fen '{fen}' has castling rights '{castling}'"));
+        }
+
+        if let Some(&en_passant) = fields.get(3) {
+            let mut chars = en_passant.chars();
+            let valid_en_passant = en_passant == "-"
+                || (en_passant.len() == 2
+                    && matches!(chars.next(), Some('a'..='h'))
+                    && matches!(chars.next(), Some('3' | '6')));
+
+            if !valid_en_passant {
+                return Err(format!("fen '{fen}' has en passant square '{en_passant}'"));
+            }
+        }
+
+        if fields
+            .iter()
+            .skip(4)
+            .any(|clock| clock.parse::<u16>().is_err())
+        {
+            return Err(format!(
+                "fen '{fen}' has a move counter that isn't a number"
+            ));
+        }
+
+        Ok(())
+    }
+
     // fen string is considered accurate at this point
     // determining the fen strings accuracy is the job of the uci
     pub fn new(&mut self, fen: &str) {
@@ -88,16 +206,17 @@ impl Board {
         for (i, section) in ranks.iter().rev().enumerate() {
             let rank = Rank::from(i as u8);
 
-            let mut cur_file = File::A;
+            // kept as a number since it runs one past the h file at the end of every rank
+            let mut cur_file = 0;
             for char in section.chars() {
                 if char.is_numeric() {
                     let num_skipped_files = char.to_digit(10).unwrap() as u8;
-                    cur_file.add(num_skipped_files);
+                    cur_file += num_skipped_files;
                     continue;
                 }
 
-                let cur_square = Square::from((cur_file, rank));
-                cur_file.plus();
+                let cur_square = Square::from((File::from(cur_file), rank));
+                cur_file += 1;
                 let piece = char_to_piece(char).unwrap();
 
                 self.add_piece::<IGNORE_ZOBRIST>(piece, cur_square);
@@ -124,14 +243,15 @@ impl Board {
 
         self.update_occupancy();
         self.zobrist = ZOBRIST.hash_from_board(&self);
+        self.checkers = self.compute_checkers();
+
+        for square in bits::all_squares(self.occupancy) {
+            self.toggle_partial_zobrists(self.piece_at(square), square);
+        }
     }
 
     pub fn is_repetition(&self) -> bool {
-        let Some(board_states) = self.past_board_states() else {
-            return false;
-        };
-
-        for board_state in board_states.iter().rev().skip(0).step_by(1) {
+        for board_state in self.past_board_states().rev() {
             if board_state.zobrist == self.zobrist {
                 return true;
             }
@@ -143,21 +263,10 @@ impl Board {
         return false;
     }
 
-    pub fn set_in_check(&mut self, in_check: bool) {
-        self.in_check = in_check;
-    }
-
-    pub fn in_check(&self) -> bool {
-        self.in_check
-    }
-
     pub fn last_move(&self) -> Option<MovePly> {
-        if self.cur_board_state == 0 {
-            return None;
-        }
-
-        let last_move = self.board_states[self.cur_board_state - 1].played;
-        Some(last_move)
+        self.past_board_states()
+            .next_back()
+            .map(|board_state| board_state.played)
     }
 
     pub fn orthogonal_bitboard_them<const COLOR: bool>(&self) -> u64 {
@@ -288,18 +397,276 @@ impl Board {
         self.zobrist
     }
 
-    pub fn past_board_states(&self) -> Option<&[BoardState]> {
-        if self.cur_board_state > 0 {
-            return Some(&self.board_states[..(self.cur_board_state)]);
-        }
+    pub fn pawn_zobrist(&self) -> u64 {
+        self.pawn_zobrist
+    }
+
+    pub fn non_pawn_zobrist(&self, color: Color) -> u64 {
+        self.non_pawn_zobrist[color as usize]
+    }
 
-        None
+    pub fn minor_zobrist(&self) -> u64 {
+        self.minor_zobrist
+    }
+
+    // oldest first, the game history and then whatever has been made since the root
+    pub fn past_board_states(&self) -> impl DoubleEndedIterator<Item = &BoardState> {
+        self.game_history.iter().chain(self.board_states.iter())
     }
 
     pub fn half_move_clock(&self) -> u8 {
         self.half_move_clock
     }
 
+    // the board doesn't count full moves, so whoever tracks the game passes the number in
+    pub fn to_fen(&self, full_move: u32) -> String {
+        let mut placement = String::new();
+
+        for rank in (0..8u8).rev() {
+            let mut empty_squares = 0;
+
+            for file in 0..8u8 {
+                let piece = self.piece_at(Square::from(rank * 8 + file));
+                if !piece.is_piece() {
+                    empty_squares += 1;
+                    continue;
+                }
+
+                if empty_squares > 0 {
+                    placement += &empty_squares.to_string();
+                    empty_squares = 0;
+                }
+                placement += &piece.to_string();
+            }
+
+            if empty_squares > 0 {
+                placement += &empty_squares.to_string();
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let mut castling = String::new();
+        for (has_rights, symbol) in [
+            (self.has_short_castle_rights(Color::White), 'K'),
+            (self.has_long_castle_rights(Color::White), 'Q'),
+            (self.has_short_castle_rights(Color::Black), 'k'),
+            (self.has_long_castle_rights(Color::Black), 'q'),
+        ] {
+            if has_rights {
+                castling.push(symbol);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        // the pawn that can be taken has just moved past the third or sixth rank
+        let en_passant = match (self.en_passant_file(), self.side_to_move) {
+            (Some(file), Color::White) => format!("{file}6"),
+            (Some(file), Color::Black) => format!("{file}3"),
+            (None, _) => String::from("-"),
+        };
+
+        format!(
+            "{placement} {side} {castling} {en_passant} {} {full_move}",
+            self.half_move_clock
+        )
+    }
+
+    pub fn checkers(&self) -> u64 {
+        self.checkers
+    }
+
+    pub fn in_check(&self) -> bool {
+        self.checkers != 0
+    }
+
+    fn compute_checkers(&self) -> u64 {
+        self.attackers_them(self.king_square(self.side_to_move), self.occupancy)
+    }
+
+    // their pieces that would attack `square` with `occupancy` on the board
+    fn attackers_them(&self, square: Square, occupancy: u64) -> u64 {
+        let orthogonal = self.bitboard_them(BasePiece::Rook) | self.bitboard_them(BasePiece::Queen);
+        let diagonal = self.bitboard_them(Bishop) | self.bitboard_them(BasePiece::Queen);
+
+        (MOVEMENT_MASKS.pawn_attacks(self.side_to_move, square) & self.bitboard_them(Pawn))
+            | (MOVEMENT_MASKS.knight[square as usize] & self.bitboard_them(Knight))
+            | (MOVEMENT_MASKS.king[square as usize] & self.bitboard_them(BasePiece::King))
+            | (rook_lookup(square, occupancy) & orthogonal)
+            | (bishop_lookup(square, occupancy) & diagonal)
+    }
+
+    /// Whether `played` moves one of our pieces the way that piece moves, ignoring pins and checks.
+    /// Lets a move from the transposition table or a killer slot be trusted without a move list.
+    pub fn is_pseudo_legal(&self, played: MovePly) -> bool {
+        if !played.has_valid_flag() {
+            return false;
+        }
+
+        let us = self.side_to_move;
+        let from = played.from();
+        let to = played.to();
+        let piece = self.piece_at(from);
+        let flag = played.flag();
+
+        if !piece.is_piece() || piece.color() != us || to.mask() & self.occupancy_us() != 0 {
+            return false;
+        }
+
+        if flag.is_castles() {
+            return self.is_pseudo_legal_castle(from, to, flag);
+        }
+
+        let base_piece = BasePiece::from(piece);
+        if base_piece != Pawn {
+            let attacks = match base_piece {
+                Knight => MOVEMENT_MASKS.knight[from as usize],
+                BasePiece::King => MOVEMENT_MASKS.king[from as usize],
+                slider => slider_lookup(slider, from, self.occupancy),
+            };
+
+            return flag == MoveFlag::None && attacks & to.mask() != 0;
+        }
+
+        let single_push = match us {
+            Color::White => from.mask() << 8,
+            Color::Black => from.mask() >> 8,
+        };
+        let captures = MOVEMENT_MASKS.pawn_attacks(us, from);
+
+        match flag {
+            MoveFlag::DoubleJump => {
+                let double_push = match us {
+                    Color::White => single_push << 8,
+                    Color::Black => single_push >> 8,
+                };
+
+                from.rank().is_pawn_start(us)
+                    && to.mask() == double_push
+                    && (single_push | double_push) & self.occupancy == 0
+            }
+            MoveFlag::EnPassantCapture => {
+                let Some(file) = self.en_passant_file() else {
+                    return false;
+                };
+                let rank = match us {
+                    Color::White => Rank::Sixth,
+                    Color::Black => Rank::Third,
+                };
+
+                to == Square::from((file, rank)) && captures & to.mask() != 0
+            }
+            // pawns reaching the last rank have to promote and nothing else can
+            _ if flag.is_promotion() != to.rank().is_pawn_promotion(us) => false,
+            _ => {
+                (to.mask() == single_push && single_push & self.occupancy == 0)
+                    || captures & to.mask() & self.occupancy_them() != 0
+            }
+        }
+    }
+
+    fn is_pseudo_legal_castle(&self, from: Square, to: Square, flag: MoveFlag) -> bool {
+        let us = self.side_to_move;
+        let (king_start, short_to, long_to, short_empty, long_empty) = match us {
+            Color::White => (
+                Square::E1,
+                Square::G1,
+                Square::C1,
+                WHITE_SHORT_NOT_ATTACKED_SQUARES,
+                WHITE_LONG_NOT_OCCUPIED_SQUARES,
+            ),
+            Color::Black => (
+                Square::E8,
+                Square::G8,
+                Square::C8,
+                BLACK_SHORT_NOT_ATTACKED_SQUARES,
+                BLACK_LONG_NOT_OCCUPIED_SQUARES,
+            ),
+        };
+
+        if from != king_start || !self.piece_at(from).is_king() {
+            return false;
+        }
+
+        match flag {
+            MoveFlag::CastleShort => {
+                to == short_to
+                    && self.has_short_castle_rights(us)
+                    && short_empty & self.occupancy == 0
+            }
+            _ => {
+                to == long_to && self.has_long_castle_rights(us) && long_empty & self.occupancy == 0
+            }
+        }
+    }
+
+    /// Whether a move that already passed `is_pseudo_legal` also keeps our king out of check.
+    pub fn is_legal(&self, played: MovePly) -> bool {
+        let us = self.side_to_move;
+        let from = played.from();
+        let to = played.to();
+        let flag = played.flag();
+        let king_square = self.king_square(us);
+
+        if flag.is_castles() {
+            let king_path = match (us, flag) {
+                (Color::White, MoveFlag::CastleShort) => WHITE_SHORT_NOT_ATTACKED_SQUARES,
+                (Color::White, _) => WHITE_LONG_NOT_ATTACKED_SQUARES,
+                (Color::Black, MoveFlag::CastleShort) => BLACK_SHORT_NOT_ATTACKED_SQUARES,
+                (Color::Black, _) => BLACK_LONG_NOT_ATTACKED_SQUARES,
+            };
+
+            return !self.in_check()
+                && bits::all_squares(king_path)
+                    .into_iter()
+                    .all(|square| self.attackers_them(square, self.occupancy) == 0);
+        }
+
+        // the king is taken off the board so it can't hide from a slider behind itself
+        if from == king_square {
+            return self.attackers_them(to, self.occupancy ^ from.mask()) & !to.mask() == 0;
+        }
+
+        // both pawns leave the rank at once, which the pins don't account for
+        if flag.is_en_passant_capture() {
+            let captured = match us {
+                Color::White => to.mask() >> 8,
+                Color::Black => to.mask() << 8,
+            };
+            let occupancy = (self.occupancy ^ from.mask() ^ captured) | to.mask();
+
+            return self.attackers_them(king_square, occupancy) & !captured == 0;
+        }
+
+        if self.checkers != 0 {
+            if bits::count(self.checkers) > 1 {
+                return false;
+            }
+
+            let checker = bits::next(self.checkers);
+            let blocking_squares =
+                IN_BETWEEN.in_between[king_square as usize][checker as usize] | self.checkers;
+
+            if to.mask() & blocking_squares == 0 {
+                return false;
+            }
+        }
+
+        // a pinned piece has to stay on the line between the king and whatever pins it
+        let pinned = move_generator::check_info(self).pinned;
+        from.mask() & pinned == 0
+            || IN_BETWEEN.in_between[king_square as usize][to as usize] & from.mask() != 0
+            || IN_BETWEEN.in_between[king_square as usize][from as usize] & to.mask() != 0
+    }
+
     pub fn update_occupancy(&mut self) {
         self.white_occupancy = 0;
         self.black_occupancy = 0;
@@ -319,7 +686,7 @@ impl Board {
     }
 
     fn push_board_state(&mut self, played: MovePly, captured: Piece) {
-        self.board_states[self.cur_board_state] = BoardState {
+        self.board_states.push(BoardState {
             played,
             captured,
             half_move_clock: self.half_move_clock,
@@ -327,10 +694,39 @@ impl Board {
             en_passant_file: self.en_passant_file,
             can_en_passant: self.can_en_passant,
             zobrist: self.zobrist,
-            in_check: self.in_check,
-        };
-        self.cur_board_state += 1;
+            checkers: self.checkers,
+            pawn_zobrist: self.pawn_zobrist,
+            non_pawn_zobrist: self.non_pawn_zobrist,
+            minor_zobrist: self.minor_zobrist,
+        });
+    }
+
+    // once the search stack is empty the moves come back off the game history
+    fn pop_board_state(&mut self) -> BoardState {
+        match self.board_states.pop() {
+            Some(board_state) => board_state,
+            None => Arc::make_mut(&mut self.game_history)
+                .pop()
+                .expect("no move to undo"),
+        }
     }
+
+    #[inline(always)]
+    fn toggle_partial_zobrists(&mut self, piece: Piece, square: Square) {
+        let square_zobrist = ZOBRIST.square_zobrist(piece, square);
+
+        match BasePiece::from(piece) {
+            Pawn => self.pawn_zobrist ^= square_zobrist,
+            base_piece => {
+                self.non_pawn_zobrist[piece.color() as usize] ^= square_zobrist;
+
+                if base_piece == Knight || base_piece == Bishop {
+                    self.minor_zobrist ^= square_zobrist;
+                }
+            }
+        }
+    }
+
     #[inline(always)]
     fn add_piece<const ZOBRIST_OPTION: bool>(&mut self, piece: Piece, square: Square) {
         self.bitboards[piece as usize].add_piece(square);
@@ -344,6 +740,7 @@ impl Board {
 
         if ZOBRIST_OPTION == INCREMENT_ZOBRIST {
             self.zobrist ^= ZOBRIST.square_zobrist(piece, square);
+            self.toggle_partial_zobrists(piece, square);
         }
     }
     #[inline(always)]
@@ -359,6 +756,7 @@ impl Board {
 
         if ZOBRIST_OPTION == INCREMENT_ZOBRIST {
             self.zobrist ^= ZOBRIST.square_zobrist(piece, square);
+            self.toggle_partial_zobrists(piece, square);
         }
     }
     #[inline(always)]
@@ -383,6 +781,8 @@ impl Board {
         if ZOBRIST_OPTION == INCREMENT_ZOBRIST {
             self.zobrist ^= ZOBRIST.square_zobrist(piece, from);
             self.zobrist ^= ZOBRIST.square_zobrist(piece, to);
+            self.toggle_partial_zobrists(piece, from);
+            self.toggle_partial_zobrists(piece, to);
         }
     }
 
@@ -568,18 +968,19 @@ impl Board {
         if ZOBRIST_OPTION {
             self.zobrist ^= ZOBRIST.side_to_move();
         }
+
+        self.checkers = self.compute_checkers();
     }
 
     #[inline(always)]
     pub fn undo_move(&mut self) {
-        let last_board_state = self.board_states[self.cur_board_state - 1];
+        let last_board_state = self.pop_board_state();
         let last_played = last_board_state.played;
 
         self.castling_rights = last_board_state.castling_rights;
         self.en_passant_file = last_board_state.en_passant_file;
         self.can_en_passant = last_board_state.can_en_passant;
         self.half_move_clock = last_board_state.half_move_clock;
-        self.in_check = last_board_state.in_check;
 
         self.side_to_move = !self.side_to_move;
 
@@ -596,9 +997,18 @@ impl Board {
             self.add_piece::<IGNORE_ZOBRIST>(last_board_state.captured, last_played.to());
         }
 
-        self.cur_board_state -= 1;
-
         self.zobrist = last_board_state.zobrist;
+        self.checkers = last_board_state.checkers;
+        self.pawn_zobrist = last_board_state.pawn_zobrist;
+        self.non_pawn_zobrist = last_board_state.non_pawn_zobrist;
+        self.minor_zobrist = last_board_state.minor_zobrist;
+    }
+
+    /// Makes `played` part of the game rather than a search, so games of any length fit
+    /// and the history is shared instead of copied when the board is cloned.
+    pub fn play(&mut self, played: &MovePly) {
+        self.make_move::<true>(played);
+        Arc::make_mut(&mut self.game_history).append(&mut self.board_states);
     }
 
     pub fn make_null_move(&mut self) {
@@ -606,19 +1016,20 @@ impl Board {
         self.zobrist ^= ZOBRIST.side_to_move();
         self.side_to_move = !self.side_to_move;
         self.can_en_passant = false;
+        // null moves are never made in check, so the other side can't be either
+        self.checkers = 0;
     }
 
     pub fn undo_null_move(&mut self) {
-        let last_board_state = self.board_states[self.cur_board_state - 1];
+        let last_board_state = self.pop_board_state();
 
         self.castling_rights = last_board_state.castling_rights;
         self.en_passant_file = last_board_state.en_passant_file;
         self.can_en_passant = last_board_state.can_en_passant;
         self.half_move_clock = last_board_state.half_move_clock;
         self.zobrist = last_board_state.zobrist;
-        self.in_check = last_board_state.in_check;
+        self.checkers = last_board_state.checkers;
 
-        self.cur_board_state -= 1;
         self.side_to_move = !self.side_to_move;
     }
 }
@@ -656,3 +1067,85 @@ impl Display for Board {
         write!(f, "{}", pretty_print)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::move_ply;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_fen_round_trips() {
+        const FENS: [&str; 4] = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 41",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 0 8",
+        ];
+
+        for fen in FENS {
+            let full_move = fen.rsplit(' ').next().unwrap().parse().unwrap();
+            assert_eq!(Board::from_fen(fen).to_fen(full_move), fen);
+        }
+
+        let mut board = Board::from_fen(FENS[0]);
+        board.play(&move_ply::find_legal_move("e2e4", &board).unwrap());
+        board.play(&move_ply::find_legal_move("g8f6", &board).unwrap());
+        board.play(&move_ply::find_legal_move("e4e5", &board).unwrap());
+        board.play(&move_ply::find_legal_move("d7d5", &board).unwrap());
+        assert_eq!(
+            board.to_fen(3),
+            "rnbqkb1r/ppp1pppp/5n2/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"
+        );
+    }
+
+    // plays random games and checks every generated move plus a pile of made up ones
+    // against what the move generator thinks is legal
+    #[test]
+    fn test_move_validation_matches_generation() {
+        const FENS: [&str; 5] = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+
+        for fen in FENS {
+            for _ in 0..40 {
+                let mut board = Board::from_fen(fen);
+
+                for _ in 0..60 {
+                    let legal = move_generator::legal_moves(&board);
+                    if legal.is_empty() {
+                        break;
+                    }
+
+                    for &played in &legal {
+                        assert!(board.is_pseudo_legal(played) && board.is_legal(played));
+                    }
+
+                    let our_pieces = bits::all_squares(board.occupancy_us());
+                    for _ in 0..200 {
+                        let from = match rng.gen_range(0, 4) {
+                            0 => Square::from(rng.gen_range(0u8, 64)),
+                            _ => our_pieces[rng.gen_range(0, our_pieces.len())],
+                        };
+                        let to = Square::from(rng.gen_range(0u8, 64));
+                        let flag = rng.gen_range(0u16, 16);
+                        let played: MovePly = (flag << 12 | (to as u16) << 6 | from as u16).into();
+
+                        let accepted = board.is_pseudo_legal(played) && board.is_legal(played);
+                        assert_eq!(accepted, legal.contains(&played), "{fen} {played}");
+                    }
+
+                    let next = legal[rng.gen_range(0, legal.len())];
+                    board.make_move::<true>(&next);
+                }
+            }
+        }
+    }
+}
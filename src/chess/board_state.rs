@@ -13,7 +13,10 @@ pub struct BoardState {
     pub en_passant_file: File,
     pub can_en_passant: bool,
     pub zobrist: u64,
-    pub in_check: bool,
+    pub checkers: u64,
+    pub pawn_zobrist: u64,
+    pub non_pawn_zobrist: [u64; 2],
+    pub minor_zobrist: u64,
 }
 
 impl Default for BoardState {
@@ -26,7 +29,10 @@ impl Default for BoardState {
             en_passant_file: File::default(),
             can_en_passant: false,
             zobrist: 0,
-            in_check: false,
+            checkers: 0,
+            pawn_zobrist: 0,
+            non_pawn_zobrist: [0; 2],
+            minor_zobrist: 0,
         }
     }
 }
@@ -3,7 +3,6 @@ pub const NUM_PIECES: usize = 12;
 pub const NUM_BASE_PIECES: usize = 6;
 pub const NUM_FILES: usize = 8;
 
-pub const MAX_MOVES: usize = 512;
 pub const MAX_DEPTH: usize = 256;
 pub const MAX_POSITIONAL_MOVES: usize = 256;
 
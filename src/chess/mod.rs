@@ -5,4 +5,5 @@ pub mod consts;
 pub mod move_generator;
 pub mod move_list;
 pub mod move_ply;
+pub mod san;
 pub mod types;
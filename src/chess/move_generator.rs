@@ -1,6 +1,7 @@
 use crate::chess::board::Board;
 use crate::chess::consts::NUM_SQUARES;
-use crate::chess::move_list::PieceMoves;
+use crate::chess::move_list::{MoveList, PieceMoves};
+use crate::chess::move_ply::MovePly;
 use crate::chess::types::color::Color;
 use crate::chess::types::move_flag::MoveFlag;
 use crate::chess::types::piece::BasePiece;
@@ -34,17 +35,70 @@ pub const BLACK_LONG_NOT_ATTACKED_SQUARES: u64 = 0xC00000000000000;
 pub const WHITE_LONG_NOT_OCCUPIED_SQUARES: u64 = 14;
 pub const BLACK_LONG_NOT_OCCUPIED_SQUARES: u64 = 0xE00000000000000;
 
-impl<const GENERATOR_TYPE: bool> MoveGenerator<GENERATOR_TYPE> {
-    pub fn generate(board: *mut Board, move_iter: &mut impl FnMut(PieceMoves)) {
-        let board: &mut Board = unsafe { &mut (*board) };
+/// What the side to move's king is up against, handed back alongside its moves.
+#[derive(Copy, Clone, Default)]
+pub struct CheckInfo {
+    /// Enemy pieces giving check.
+    pub checkers: u64,
+    /// Our pieces that can only move along the line between the king and the piece pinning them.
+    pub pinned: u64,
+}
+
+impl CheckInfo {
+    pub fn in_check(&self) -> bool {
+        self.checkers != 0
+    }
+}
 
+/// Every legal move in the position.
+pub fn legal_moves(board: &Board) -> Vec<MovePly> {
+    MoveGenerator::<GEN_ALL>::iter(board).collect()
+}
+
+/// The check and pin half of move generation, for when the moves themselves aren't needed.
+pub fn check_info(board: &Board) -> CheckInfo {
+    match board.side_to_move() {
+        Color::White => MoveGenerator::<GEN_ALL>::check_info_for::<WHITE>(board),
+        Color::Black => MoveGenerator::<GEN_ALL>::check_info_for::<BLACK>(board),
+    }
+}
+
+impl<const GENERATOR_TYPE: bool> MoveGenerator<GENERATOR_TYPE> {
+    /// Hands the moves over a piece at a time, which lets bulk counting skip unpacking them.
+    pub fn generate(board: &Board, move_iter: &mut impl FnMut(PieceMoves)) -> CheckInfo {
         match board.side_to_move() {
             Color::White => Self::generator::<WHITE>(board, move_iter),
             Color::Black => Self::generator::<BLACK>(board, move_iter),
         }
     }
 
-    fn generator<const COLOR: bool>(board: &mut Board, move_iter: &mut impl FnMut(PieceMoves)) {
+    /// Appends the moves to `move_list`.
+    pub fn fill(board: &Board, move_list: &mut MoveList) -> CheckInfo {
+        Self::generate(board, &mut |mut piece_moves| {
+            move_list.add_piece_moves(&mut piece_moves);
+        })
+    }
+
+    pub fn iter(board: &Board) -> impl Iterator<Item = MovePly> + use<GENERATOR_TYPE> {
+        let mut move_list = MoveList::default();
+        Self::fill(board, &mut move_list);
+        move_list.into_iter()
+    }
+
+    fn check_info_for<const COLOR: bool>(board: &Board) -> CheckInfo {
+        let mut pin_ray_mask: [u64; PIN_RAY_MASK_SIZE] = [u64::MAX; PIN_RAY_MASK_SIZE];
+        pin_ray_mask[PIN_RAY_MASK_SIZE - 1] = 0;
+
+        CheckInfo {
+            checkers: board.checkers(),
+            pinned: Self::get_pins::<COLOR>(board, &mut pin_ray_mask),
+        }
+    }
+
+    fn generator<const COLOR: bool>(
+        board: &Board,
+        move_iter: &mut impl FnMut(PieceMoves),
+    ) -> CheckInfo {
         let (pieces_checking, allowed_squares) = Self::get_check_data::<COLOR>(board);
 
         let mut pin_ray_mask: [u64; PIN_RAY_MASK_SIZE] = [u64::MAX; PIN_RAY_MASK_SIZE];
@@ -52,12 +106,6 @@ impl<const GENERATOR_TYPE: bool> MoveGenerator<GENERATOR_TYPE> {
 
         let pinned_pieces_mask = Self::get_pins::<COLOR>(board, &mut pin_ray_mask);
 
-        if pieces_checking != 0 {
-            board.set_in_check(true);
-        } else {
-            board.set_in_check(false);
-        }
-
         Self::update_pawn_moves::<COLOR>(
             board,
             move_iter,
@@ -79,6 +127,11 @@ impl<const GENERATOR_TYPE: bool> MoveGenerator<GENERATOR_TYPE> {
         );
         Self::update_slider_moves::<COLOR>(Rook, board, move_iter, allowed_squares, &pin_ray_mask);
         Self::update_slider_moves::<COLOR>(Queen, board, move_iter, allowed_squares, &pin_ray_mask);
+
+        CheckInfo {
+            checkers: pieces_checking,
+            pinned: pinned_pieces_mask,
+        }
     }
 
     fn pop_lsb(b: &mut u64) -> u32 {
@@ -170,19 +223,8 @@ impl<const GENERATOR_TYPE: bool> MoveGenerator<GENERATOR_TYPE> {
     fn get_check_data<const COLOR: bool>(board: &Board) -> (u64, u64) {
         let king_square = board.king_square_const::<COLOR>();
 
-        let enemy_orthogonal = board.orthogonal_bitboard_them::<COLOR>();
-        let enemy_diagonal = board.diagonal_bitboard_them::<COLOR>();
-
-        let knight_checks =
-            MOVEMENT_MASKS.knight[king_square as usize] & board.bitboard(Knight, (!COLOR).into());
-        let pawn_checks = MOVEMENT_MASKS.pawn_attacks_const::<COLOR>(king_square)
-            & board.bitboard(Pawn, (!COLOR).into());
-
-        let regular_check: u64 = knight_checks | pawn_checks;
-        let orthogonal_check: u64 = rook_lookup(king_square, board.occupancy()) & enemy_orthogonal;
-        let diagonal_check: u64 = bishop_lookup(king_square, board.occupancy()) & enemy_diagonal;
-
-        let all_checks = orthogonal_check | diagonal_check | regular_check;
+        // the board keeps its checkers up to date in make_move
+        let all_checks = board.checkers();
         let mut allowed_squares: u64 = 0;
 
         if bits::count(all_checks) == 1 {
@@ -2,7 +2,9 @@ use crate::chess::move_ply::MovePly;
 use crate::chess::types::move_flag::MoveFlag;
 use crate::chess::types::square::Square;
 use crate::general::bits;
+use std::array;
 use std::cmp::Reverse;
+use std::iter::Take;
 use std::slice::Iter;
 
 const IS_BULK: u8 = 0b00000010;
@@ -92,7 +94,7 @@ impl Iterator for PieceMoves {
 
             let pop_to_mask = (self.is_promotion() && self.flag() == MoveFlag::PromoteToQueen)
                 || !self.is_promotion();
-            self.to_mask &= (self.to_mask - 1) | (pop_to_mask as u64 - 1);
+            self.to_mask &= (self.to_mask - 1) | (pop_to_mask as u64).wrapping_sub(1);
 
             let flag = self.flag();
 
@@ -212,14 +214,13 @@ impl MoveList {
             self.moves[j] = original_copy[i];
         }
     }
+}
 
-    pub fn contains_move(&self, checked_move: MovePly) -> bool {
-        for cur_move in self.iter() {
-            if *cur_move == checked_move {
-                return true;
-            }
-        }
+impl IntoIterator for MoveList {
+    type Item = MovePly;
+    type IntoIter = Take<array::IntoIter<MovePly, 256>>;
 
-        false
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.into_iter().take(self.move_count)
     }
 }
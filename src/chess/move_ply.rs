@@ -1,4 +1,5 @@
 use crate::chess::board::Board;
+use crate::chess::move_generator;
 use crate::chess::types::color::Color::Black;
 use crate::chess::types::move_flag::MoveFlag;
 use crate::chess::types::square::Square;
@@ -55,6 +56,11 @@ impl MovePly {
     pub fn is_default(&self) -> bool {
         self.packed_data == 0
     }
+
+    // a move read back from the table can hold any 16 bits, not all of which are flags
+    pub fn has_valid_flag(&self) -> bool {
+        self.packed_data >> FLAG_SHIFT <= MoveFlag::CastleShort as u16
+    }
 }
 
 impl Display for MovePly {
@@ -122,3 +128,10 @@ pub fn uci_move_parser(played: &str, board: &Board) -> MovePly {
 
     MovePly::new(from, to, flag)
 }
+
+// unlike uci_move_parser this checks the move against the legal moves, for input that can't be trusted
+pub fn find_legal_move(played: &str, board: &Board) -> Option<MovePly> {
+    move_generator::legal_moves(board)
+        .into_iter()
+        .find(|legal_move| legal_move.to_string() == played)
+}
@@ -0,0 +1,98 @@
+use crate::chess::board::Board;
+use crate::chess::move_generator::{self, GEN_ALL, MoveGenerator};
+use crate::chess::move_list::MoveList;
+use crate::chess::move_ply::MovePly;
+use crate::chess::types::color::Color::White;
+use crate::chess::types::move_flag::MoveFlag;
+use crate::chess::types::piece::BasePiece;
+
+// standard algebraic notation for a legal move in the given position
+pub fn to_san(board: &Board, played: &MovePly) -> String {
+    let from = played.from();
+    let to = played.to();
+    let flag = played.flag();
+
+    let mut san = match flag {
+        MoveFlag::CastleShort => String::from("O-O"),
+        MoveFlag::CastleLong => String::from("O-O-O"),
+        _ => {
+            let base_piece = BasePiece::from(board.piece_at(from));
+            let is_capture = board.piece_at(to).is_piece() || flag.is_en_passant_capture();
+            let mut san = String::new();
+
+            if base_piece == BasePiece::Pawn {
+                if is_capture {
+                    san.push_str(&from.file().to_string());
+                }
+            } else {
+                san.push_str(&base_piece.to_string());
+                san.push_str(&disambiguation(board, played, base_piece));
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+
+            if flag.is_promotion() {
+                let promotion_piece = BasePiece::from(flag.promotion_piece(White));
+                san.push_str(&format!("={promotion_piece}"));
+            }
+
+            san
+        }
+    };
+
+    let mut board = board.clone();
+    board.make_move::<true>(played);
+
+    if board.in_check() {
+        let mut replies = MoveList::default();
+        MoveGenerator::<GEN_ALL>::fill(&board, &mut replies);
+        san.push(if replies.move_count() == 0 { '#' } else { '+' });
+    }
+
+    san
+}
+
+// the file if it tells the pieces apart, then the rank, and the whole square if neither does
+fn disambiguation(board: &Board, played: &MovePly, base_piece: BasePiece) -> String {
+    let from = played.from();
+
+    let rivals: Vec<MovePly> = move_generator::legal_moves(board)
+        .into_iter()
+        .filter(|legal_move| {
+            legal_move.to() == played.to()
+                && legal_move.from() != from
+                && BasePiece::from(board.piece_at(legal_move.from())) == base_piece
+        })
+        .collect();
+
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals
+        .iter()
+        .all(|rival| rival.from().file() != from.file())
+    {
+        from.file().to_string()
+    } else if rivals
+        .iter()
+        .all(|rival| rival.from().rank() != from.rank())
+    {
+        from.rank().to_string()
+    } else {
+        from.to_string()
+    }
+}
+
+pub fn line_to_san(board: &Board, line: &[MovePly]) -> Vec<String> {
+    let mut board = board.clone();
+
+    line.iter()
+        .map(|played| {
+            let san = to_san(&board, played);
+            board.make_move::<true>(played);
+            san
+        })
+        .collect()
+}
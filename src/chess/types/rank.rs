@@ -1,7 +1,7 @@
 use crate::chess::types::color::Color;
 use std::fmt::{Display, Formatter};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 #[repr(u8)]
 pub enum Rank {
     First,
@@ -9,27 +9,30 @@ pub struct Arbiter {}
 
 impl Arbiter {
     pub fn arbitrate(board: &Board, move_list: &MoveList) -> MatchResult {
-        if Self::is_checkmate(board, move_list) {
+        if Self::is_checkmate(move_list, board.in_check()) {
             return MatchResult::Loss;
         }
 
-        if Self::is_insufficient_material(board)
-            || Self::is_stalemate(board, move_list)
-            || Self::is_fifty_move_rule(board)
-            || Self::is_three_fold(board)
-        {
+        if Self::is_stalemate(move_list, board.in_check()) || Self::is_rule_draw(board) {
             return MatchResult::Draw;
         }
 
         MatchResult::NoResult
     }
 
-    fn is_stalemate(board: &Board, move_list: &MoveList) -> bool {
-        move_list.move_count() == 0 && !board.in_check()
+    // the draws that can be seen without knowing the legal moves
+    pub fn is_rule_draw(board: &Board) -> bool {
+        Self::is_insufficient_material(board)
+            || Self::is_fifty_move_rule(board)
+            || Self::is_three_fold(board)
     }
 
-    fn is_checkmate(board: &Board, move_list: &MoveList) -> bool {
-        move_list.move_count() == 0 && board.in_check()
+    fn is_stalemate(move_list: &MoveList, in_check: bool) -> bool {
+        move_list.move_count() == 0 && !in_check
+    }
+
+    fn is_checkmate(move_list: &MoveList, in_check: bool) -> bool {
+        move_list.move_count() == 0 && in_check
     }
 
     fn is_fifty_move_rule(board: &Board) -> bool {
@@ -68,17 +71,15 @@ impl Arbiter {
         let mut position_count = HashMap::new();
         position_count.insert(board.zobrist(), 1);
 
-        if let Some(past_states) = board.past_board_states() {
-            for past_board_state in past_states.iter().rev() {
-                *position_count.entry(past_board_state.zobrist).or_insert(0) += 1;
+        for past_board_state in board.past_board_states().rev() {
+            *position_count.entry(past_board_state.zobrist).or_insert(0) += 1;
 
-                if position_count[&past_board_state.zobrist] >= 3 {
-                    return true;
-                }
+            if position_count[&past_board_state.zobrist] >= 3 {
+                return true;
+            }
 
-                if past_board_state.half_move_clock == 0 {
-                    break;
-                }
+            if past_board_state.half_move_clock == 0 {
+                break;
             }
         }
         false
@@ -4,6 +4,9 @@ use crate::chess::move_ply::MovePly;
 use crate::chess::types::piece::{BasePiece, Piece};
 use crate::chess::types::square::Square;
 
+// the history is shrunk by this factor between searches so older moves fade out
+const DECAY_DIVISOR: i16 = 2;
+
 pub struct CaptureHeuristics {
     values: [[[i16; consts::NUM_BASE_PIECES]; consts::NUM_PIECES]; consts::NUM_SQUARES],
 }
@@ -58,6 +61,12 @@ impl CaptureHeuristics {
         self.update_value(bonus, best_move.to(), capturing_piece, captured_piece);
     }
 
+    pub fn decay(&mut self) {
+        for value in self.values.iter_mut().flatten().flatten() {
+            *value /= DECAY_DIVISOR;
+        }
+    }
+
     pub fn get(&self, target: Square, capturing_piece: Piece, captured_piece: Piece) -> i16 {
         let captured_piece: BasePiece = captured_piece.into();
 
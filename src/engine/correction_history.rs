@@ -0,0 +1,91 @@
+use crate::chess::board::Board;
+use crate::chess::types::color::Color;
+use crate::engine::score::TB_WIN_IN_MAX_PLY;
+
+const CORRECTION_HISTORY_SIZE: usize = 16384;
+
+// entries are stored in 1/CORRECTION_GRAIN centipawns so small updates are not rounded away
+const CORRECTION_GRAIN: i32 = 32;
+const CORRECTION_LIMIT: i32 = 8192;
+const MAX_BONUS: i32 = CORRECTION_LIMIT / 4;
+
+const PAWN_WEIGHT: i32 = 2;
+const MINOR_WEIGHT: i32 = 1;
+const NON_PAWN_WEIGHT: i32 = 1;
+const WEIGHT_DIVISOR: i32 = 4;
+
+// the corrected eval should never be mistaken for a mate score
+const MAX_CORRECTED_EVAL: i16 = TB_WIN_IN_MAX_PLY - 1;
+
+type CorrectionTable = [[i16; CORRECTION_HISTORY_SIZE]; 2];
+
+// learns how far the static eval tends to be from the search score in similar positions
+// positions are considered similar if they share their pawn structure, their minor pieces
+// or the non pawn material of either side
+pub struct CorrectionHistory {
+    pawn: CorrectionTable,
+    minor: CorrectionTable,
+    non_pawn: [CorrectionTable; 2],
+}
+
+impl Default for CorrectionHistory {
+    fn default() -> Self {
+        Self {
+            pawn: [[0; CORRECTION_HISTORY_SIZE]; 2],
+            minor: [[0; CORRECTION_HISTORY_SIZE]; 2],
+            non_pawn: [[[0; CORRECTION_HISTORY_SIZE]; 2]; 2],
+        }
+    }
+}
+
+impl CorrectionHistory {
+    fn index(zobrist: u64) -> usize {
+        zobrist as usize % CORRECTION_HISTORY_SIZE
+    }
+
+    fn update_entry(entry: &mut i16, bonus: i32) {
+        let old_value = *entry as i32;
+        let new_value = old_value + bonus - old_value * bonus.abs() / CORRECTION_LIMIT;
+
+        *entry = new_value.clamp(-CORRECTION_LIMIT, CORRECTION_LIMIT) as i16;
+    }
+
+    pub fn update(&mut self, board: &Board, depth: u8, search_score: i16, static_eval: i16) {
+        let side = board.side_to_move() as usize;
+        let difference = (search_score as i32 - static_eval as i32) * CORRECTION_GRAIN;
+        let bonus = (difference * depth as i32 / 8).clamp(-MAX_BONUS, MAX_BONUS);
+
+        Self::update_entry(
+            &mut self.pawn[side][Self::index(board.pawn_zobrist())],
+            bonus,
+        );
+        Self::update_entry(
+            &mut self.minor[side][Self::index(board.minor_zobrist())],
+            bonus,
+        );
+
+        for color in [Color::White, Color::Black] {
+            let index = Self::index(board.non_pawn_zobrist(color));
+            Self::update_entry(&mut self.non_pawn[color as usize][side][index], bonus);
+        }
+    }
+
+    pub fn correct(&self, board: &Board, raw_eval: i16) -> i16 {
+        let side = board.side_to_move() as usize;
+
+        let pawn = self.pawn[side][Self::index(board.pawn_zobrist())] as i32;
+        let minor = self.minor[side][Self::index(board.minor_zobrist())] as i32;
+
+        let mut non_pawn = 0;
+        for color in [Color::White, Color::Black] {
+            let index = Self::index(board.non_pawn_zobrist(color));
+            non_pawn += self.non_pawn[color as usize][side][index] as i32;
+        }
+
+        let correction = (pawn * PAWN_WEIGHT + minor * MINOR_WEIGHT + non_pawn * NON_PAWN_WEIGHT)
+            / (CORRECTION_GRAIN * WEIGHT_DIVISOR);
+
+        (raw_eval as i32 + correction).clamp(-MAX_CORRECTED_EVAL as i32, MAX_CORRECTED_EVAL as i32)
+            as i16
+    }
+}
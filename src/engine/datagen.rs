@@ -0,0 +1,64 @@
+use crate::chess::types::color::Color;
+use crate::engine::score;
+use crate::engine::search_limits::SearchLimits;
+use crate::engine::selfplay::{self, Player};
+use crate::engine::skill::Skill;
+use crate::engine::time_manager::TimeManager;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// a few random moves so the games don't all follow the same line, few enough to keep them balanced
+const MIN_OPENING_PLIES: usize = 8;
+const MAX_OPENING_PLIES: usize = 9;
+
+// node limited self-play written out as "fen | eval | result" for wdl-fit, one position per line
+// the eval is the raw search score and both it and the result are from white's point of view
+// a single thread with a node limit searches the same way every time, so a seed always gives the same file
+pub fn run(seed: u64, num_games: usize, nodes: u64, path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let search_limits =
+        SearchLimits::new(TimeManager::infinite(), 1, Skill::default()).with_node_limit(nodes);
+    let player = Player::new(search_limits);
+
+    let mut num_positions = 0;
+
+    for game in 1..=num_games {
+        let Some(opening) =
+            selfplay::random_opening(&mut rng, MIN_OPENING_PLIES, MAX_OPENING_PLIES)
+        else {
+            continue;
+        };
+
+        // positions in check and mates say nothing about how an eval turns into a result
+        let mut positions = Vec::new();
+        let result = selfplay::play_game(&opening, &player, &player, |board, plies, searched| {
+            if board.in_check() || score::is_decisive(searched.score) {
+                return;
+            }
+
+            let white_eval = match board.side_to_move() {
+                Color::White => searched.score,
+                Color::Black => -searched.score,
+            };
+            positions.push((board.to_fen(plies as u32 / 2 + 1), white_eval));
+        });
+
+        for (fen, eval) in &positions {
+            writeln!(writer, "{fen} | {eval} | {:.1}", result.white_score())?;
+        }
+        writer.flush()?;
+
+        num_positions += positions.len();
+        if game % 100 == 0 {
+            println!("{game} games, {num_positions} positions");
+        }
+    }
+
+    println!("{num_games} games, {num_positions} positions");
+
+    Ok(())
+}
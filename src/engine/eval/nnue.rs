@@ -1,5 +1,5 @@
 use crate::chess::board::Board;
-use crate::chess::consts::MAX_MOVES;
+use crate::chess::consts::MAX_DEPTH;
 use crate::chess::move_ply::MovePly;
 use crate::chess::types::color::Color;
 use crate::chess::types::move_flag::MoveFlag;
@@ -12,18 +12,18 @@ use crate::general::bits;
 
 #[derive(Copy, Clone)]
 pub struct NNUE {
-    accumulator_stack: [Accumulator; MAX_MOVES],
+    // built fresh from the search root, so it only ever needs to be as deep as the search
+    accumulator_stack: [Accumulator; MAX_DEPTH],
     cur_accumulator: usize,
 }
 
 impl NNUE {
-    pub fn new(mut board: Board) -> Self {
+    pub fn new(board: &Board) -> Self {
         let mut nnue = Self {
-            accumulator_stack: [Accumulator::default(); MAX_MOVES],
+            accumulator_stack: [Accumulator::default(); MAX_DEPTH],
             cur_accumulator: 0,
         };
 
-        board.update_occupancy();
         let squares_with_pieces = bits::all_squares(board.occupancy());
         for square in squares_with_pieces {
             let piece = board.piece_at(square);
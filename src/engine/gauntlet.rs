@@ -0,0 +1,162 @@
+use crate::engine::search_limits::SearchLimits;
+use crate::engine::selfplay::{self, GameResult, Player};
+use crate::engine::skill::{MAX_SKILL_LEVEL, Skill};
+use crate::engine::time_manager::TimeManager;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// the same short random openings as datagen, every one of them is played once with each colour
+const MIN_OPENING_PLIES: usize = 8;
+const MAX_OPENING_PLIES: usize = 9;
+
+// two standard errors either side, roughly a 95% interval
+const ERROR_MARGIN: f64 = 1.96;
+
+// a score of exactly 0 or 1 has no finite elo, a handful of games can still end up there
+const MAX_SCORE: f64 = 0.999;
+
+struct PairResult {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl PairResult {
+    fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    // elo of the stronger side together with the half width of its interval
+    fn elo(&self) -> (f64, f64) {
+        let games = self.games() as f64;
+        let score = self.score();
+        let square_mean = (self.wins as f64 + 0.25 * self.draws as f64) / games;
+        let error = ((square_mean - score * score) / games).sqrt();
+
+        let low = elo_difference(score - ERROR_MARGIN * error);
+        let high = elo_difference(score + ERROR_MARGIN * error);
+
+        (elo_difference(score), (high - low) / 2.0)
+    }
+}
+
+fn elo_difference(score: f64) -> f64 {
+    let score = score.clamp(1.0 - MAX_SCORE, MAX_SCORE);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn skill_player(level: u8) -> Player {
+    Player::new(SearchLimits::new(
+        TimeManager::infinite(),
+        1,
+        Skill::from_level(level),
+    ))
+}
+
+// every skill level plays the one above it, the elo gaps between neighbours add up to a ladder starting at level 0
+// the picks of a weakened search are random, so unlike datagen a seed only fixes the openings
+pub fn run(seed: u64, games_per_pair: usize, path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    writeln!(
+        writer,
+        "gauntlet {seed} {games_per_pair}, results from the higher level's point of view"
+    )?;
+
+    let mut ladder = vec![(0.0, 0.0)];
+
+    for level in 0..MAX_SKILL_LEVEL - 1 {
+        let weaker = skill_player(level);
+        let stronger = skill_player(level + 1);
+        let mut result = PairResult {
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        };
+
+        while result.games() < games_per_pair {
+            let Some(opening) =
+                selfplay::random_opening(&mut rng, MIN_OPENING_PLIES, MAX_OPENING_PLIES)
+            else {
+                continue;
+            };
+
+            let games = [
+                selfplay::play_game(&opening, &stronger, &weaker, |_, _, _| {}),
+                match selfplay::play_game(&opening, &weaker, &stronger, |_, _, _| {}) {
+                    GameResult::WhiteWin => GameResult::BlackWin,
+                    GameResult::Draw => GameResult::Draw,
+                    GameResult::BlackWin => GameResult::WhiteWin,
+                },
+            ];
+
+            for game in games {
+                match game {
+                    GameResult::WhiteWin => result.wins += 1,
+                    GameResult::Draw => result.draws += 1,
+                    GameResult::BlackWin => result.losses += 1,
+                }
+            }
+        }
+
+        let (elo, error) = result.elo();
+        let line = format!(
+            "level {} vs {}: +{} ={} -{} score {:.3} elo {elo:+.0} +/- {error:.0}",
+            level + 1,
+            level,
+            result.wins,
+            result.draws,
+            result.losses,
+            result.score()
+        );
+        println!("{line}");
+        writeln!(writer, "{line}")?;
+        writer.flush()?;
+
+        // the errors of the gaps are independent, so they add up as variances
+        let (below_elo, below_error): (f64, f64) = ladder[level as usize];
+        ladder.push((below_elo + elo, below_error.hypot(error)));
+    }
+
+    writeln!(writer, "ladder relative to level 0")?;
+    for (level, (elo, error)) in ladder.iter().enumerate() {
+        let line = format!("level {level}: {elo:.0} +/- {error:.0}");
+        println!("{line}");
+        writeln!(writer, "{line}")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elo_difference() {
+        assert_eq!(elo_difference(0.5), 0.0);
+        assert!((elo_difference(0.75) - 190.85).abs() < 0.01);
+        assert!((elo_difference(0.25) + elo_difference(0.75)).abs() < 1e-9);
+        assert!(elo_difference(1.0).is_finite());
+    }
+
+    #[test]
+    fn test_pair_result_elo_is_zero_for_an_even_match() {
+        let result = PairResult {
+            wins: 30,
+            draws: 40,
+            losses: 30,
+        };
+
+        let (elo, error) = result.elo();
+        assert_eq!(elo, 0.0);
+        assert!(error > 0.0);
+    }
+}
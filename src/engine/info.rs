@@ -1 +1,24 @@
+use crate::chess::move_ply::MovePly;
 
+#[derive(Copy, Clone, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+// one line of an iteration as the searcher sees it, every reporter formats it its own way
+pub struct IterationInfo<'a> {
+    pub pv_idx: usize,
+    pub multi_pv: usize,
+    pub depth: u8,
+    pub sel_depth: u8,
+    pub score: i16,
+    pub bound: Bound,
+    pub wdl: (u16, u16, u16),
+    pub nodes: u64,
+    pub nps: u128,
+    pub time: u128,
+    pub hash_full: u32,
+    pub pv: &'a [MovePly],
+}
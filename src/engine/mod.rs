@@ -1,11 +1,25 @@
 pub mod arbiter;
 mod capture_history;
+mod correction_history;
 mod counter_move_heuristics;
+pub mod datagen;
 pub mod eval;
-mod info;
+pub mod gauntlet;
+pub mod info;
 pub mod perft;
+mod pv_table;
+pub mod reporter;
+mod root_move;
+pub mod score;
 pub mod search;
 mod search_funcs;
 pub mod search_limits;
+mod search_stack;
+pub mod selfplay;
+pub mod skill;
+pub mod thread_pool;
+pub mod time_manager;
 pub mod transposition;
 pub mod types;
+pub mod wdl;
+pub mod wdl_fit;
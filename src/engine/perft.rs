@@ -2,6 +2,8 @@ use crate::chess::board::Board;
 use crate::chess::move_generator::GEN_ALL;
 use crate::chess::move_generator::MoveGenerator;
 use crate::chess::move_list::MoveList;
+use crate::chess::move_ply::MovePly;
+use crate::engine::reporter::SearchReporter;
 use std::time::Instant;
 
 struct PerftTT {
@@ -51,6 +53,24 @@ pub const PERFT: u8 = 0;
 pub const BULK_PERFT: u8 = 1;
 pub const TT_PERFT: u8 = 2;
 
+fn make_move<const PERFT_TYPE: u8>(board: &mut Board, cur_move: &MovePly) {
+    if PERFT_TYPE == TT_PERFT {
+        board.make_move::<true>(cur_move);
+    } else {
+        board.make_move::<false>(cur_move);
+    }
+}
+
+// the last ply is only counted, never played
+fn count_moves(board: &Board) -> u64 {
+    let mut num_moves = 0;
+    MoveGenerator::<GEN_ALL>::generate(board, &mut |piece_moves| {
+        num_moves += piece_moves.move_count();
+    });
+
+    num_moves
+}
+
 fn search<const PERFT_TYPE: u8>(
     board: &mut Board,
     depth: u8,
@@ -63,40 +83,18 @@ fn search<const PERFT_TYPE: u8>(
         return tt_nodes;
     }
 
-    let board_ptr: *mut Board = board;
-
     if depth == 3 && (PERFT_TYPE == BULK_PERFT || PERFT_TYPE == TT_PERFT) {
-        MoveGenerator::<GEN_ALL>::generate(board_ptr, &mut |piece_moves| {
-            for cur_move in piece_moves {
-                unsafe {
-                    if PERFT_TYPE == TT_PERFT {
-                        (*board_ptr).make_move::<true>(&cur_move);
-                    } else {
-                        (*board_ptr).make_move::<false>(&cur_move);
-                    }
-                }
-                MoveGenerator::<GEN_ALL>::generate(board_ptr, &mut |first_moves| {
-                    for cur_move in first_moves {
-                        unsafe {
-                            if PERFT_TYPE == TT_PERFT {
-                                (*board_ptr).make_move::<true>(&cur_move);
-                            } else {
-                                (*board_ptr).make_move::<false>(&cur_move);
-                            }
-                        }
-                        MoveGenerator::<GEN_ALL>::generate(board_ptr, &mut |second_moves| {
-                            num_nodes += second_moves.move_count();
-                        });
-                        unsafe {
-                            (*board_ptr).undo_move();
-                        }
-                    }
-                });
-                unsafe {
-                    (*board_ptr).undo_move();
-                }
+        for cur_move in MoveGenerator::<GEN_ALL>::iter(board) {
+            make_move::<PERFT_TYPE>(board, &cur_move);
+
+            for first_move in MoveGenerator::<GEN_ALL>::iter(board) {
+                make_move::<PERFT_TYPE>(board, &first_move);
+                num_nodes += count_moves(board);
+                board.undo_move();
             }
-        });
+
+            board.undo_move();
+        }
         return num_nodes;
     }
 
@@ -104,39 +102,31 @@ fn search<const PERFT_TYPE: u8>(
         return 1;
     }
 
-    MoveGenerator::<GEN_ALL>::generate(board_ptr, &mut |piece_moves| {
-        for cur_move in piece_moves {
-            unsafe {
-                if PERFT_TYPE == TT_PERFT {
-                    (*board_ptr).make_move::<true>(&cur_move);
-                } else {
-                    (*board_ptr).make_move::<false>(&cur_move);
-                }
-            }
-
-            let search_nodes = search::<PERFT_TYPE>(board, depth - 1, 0, transposition);
+    for cur_move in MoveGenerator::<GEN_ALL>::iter(board) {
+        make_move::<PERFT_TYPE>(board, &cur_move);
 
-            if PERFT_TYPE == TT_PERFT {
-                transposition.update(board.zobrist(), search_nodes, depth - 1);
-            }
+        let search_nodes = search::<PERFT_TYPE>(board, depth - 1, 0, transposition);
 
-            num_nodes += search_nodes;
-            unsafe {
-                (*board_ptr).undo_move();
-            }
+        if PERFT_TYPE == TT_PERFT {
+            transposition.update(board.zobrist(), search_nodes, depth - 1);
         }
-    });
+
+        num_nodes += search_nodes;
+        board.undo_move();
+    }
 
     num_nodes
 }
 
-pub fn perft<const PERFT_TYPE: u8>(board: &mut Board, depth: u8) -> u64 {
+pub fn perft<const PERFT_TYPE: u8>(
+    board: &mut Board,
+    depth: u8,
+    reporter: &dyn SearchReporter,
+) -> u64 {
     let mut transposition = PerftTT::new(128);
 
     let mut start_pos_moves = MoveList::default();
-    MoveGenerator::<GEN_ALL>::generate(board, &mut |mut piece_moves| {
-        start_pos_moves.add_piece_moves(&mut piece_moves);
-    });
+    MoveGenerator::<GEN_ALL>::fill(board, &mut start_pos_moves);
 
     let timer = Instant::now();
 
@@ -145,29 +135,23 @@ pub fn perft<const PERFT_TYPE: u8>(board: &mut Board, depth: u8) -> u64 {
     if depth == 1 {
         all_nodes += start_pos_moves.move_count() as u64;
         for curr_move in start_pos_moves.iter() {
-            println!("{curr_move}");
+            reporter.perft_move(curr_move, 1);
         }
     } else {
         for curr_move in start_pos_moves.iter() {
-            if PERFT_TYPE == TT_PERFT {
-                board.make_move::<true>(curr_move);
-            } else {
-                board.make_move::<false>(curr_move);
-            }
+            make_move::<PERFT_TYPE>(board, curr_move);
 
             let num_nodes = search::<PERFT_TYPE>(board, depth - 1, 0, &mut transposition);
             all_nodes += num_nodes;
             board.undo_move();
-            println!("{curr_move}: {num_nodes}");
+            reporter.perft_move(curr_move, num_nodes);
         }
     }
 
     let nodes_per_second = all_nodes as f64 / (timer.elapsed().as_secs_f64());
     let elapsed = timer.elapsed().as_secs_f64();
 
-    println!("\nNodes searched: {all_nodes}");
-    println!("Nodes per second: {nodes_per_second:.0}");
-    println!("Seconds elapsed: {elapsed:.3}\n");
+    reporter.perft_finished(all_nodes, nodes_per_second, elapsed);
 
     all_nodes
 }
@@ -175,32 +159,51 @@ pub fn perft<const PERFT_TYPE: u8>(board: &mut Board, depth: u8) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::reporter::SilentReporter;
 
     #[test]
     fn test_perft() {
         let mut board = Board::default();
 
         board.new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-        assert_eq!(perft::<TT_PERFT>(&mut board, 7), 3_195_901_860);
+        assert_eq!(
+            perft::<TT_PERFT>(&mut board, 7, &SilentReporter),
+            3_195_901_860
+        );
 
         board = Board::default();
         board.new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ");
-        assert_eq!(perft::<TT_PERFT>(&mut board, 5), 193_690_690);
+        assert_eq!(
+            perft::<TT_PERFT>(&mut board, 5, &SilentReporter),
+            193_690_690
+        );
 
         board = Board::default();
         board.new("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
-        assert_eq!(perft::<TT_PERFT>(&mut board, 8), 3_009_794_393);
+        assert_eq!(
+            perft::<TT_PERFT>(&mut board, 8, &SilentReporter),
+            3_009_794_393
+        );
 
         board = Board::default();
         board.new("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
-        assert_eq!(perft::<TT_PERFT>(&mut board, 6), 706_045_033);
+        assert_eq!(
+            perft::<TT_PERFT>(&mut board, 6, &SilentReporter),
+            706_045_033
+        );
 
         board = Board::default();
         board.new("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8");
-        assert_eq!(perft::<TT_PERFT>(&mut board, 5), 89_941_194);
+        assert_eq!(
+            perft::<TT_PERFT>(&mut board, 5, &SilentReporter),
+            89_941_194
+        );
 
         board = Board::default();
         board.new("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10");
-        assert_eq!(perft::<TT_PERFT>(&mut board, 6), 6_923_051_137);
+        assert_eq!(
+            perft::<TT_PERFT>(&mut board, 6, &SilentReporter),
+            6_923_051_137
+        );
     }
 }
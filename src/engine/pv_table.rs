@@ -0,0 +1,52 @@
+use crate::chess::consts::MAX_DEPTH;
+use crate::chess::move_ply::MovePly;
+
+// triangular pv table, each ply holds the best line found from that ply onwards
+// whenever a move raises alpha its line becomes the move followed by the line of the ply below
+pub struct PvTable {
+    moves: [[MovePly; MAX_DEPTH]; MAX_DEPTH],
+    lengths: [usize; MAX_DEPTH],
+}
+
+impl Default for PvTable {
+    fn default() -> Self {
+        Self {
+            moves: [[MovePly::default(); MAX_DEPTH]; MAX_DEPTH],
+            lengths: [0; MAX_DEPTH],
+        }
+    }
+}
+
+impl PvTable {
+    pub fn clear(&mut self, ply: u8) {
+        self.lengths[ply as usize] = 0;
+    }
+
+    pub fn update(&mut self, ply: u8, best_move: MovePly) {
+        let ply = ply as usize;
+        let child_length = self.line_length(ply + 1);
+
+        let (parent_lines, child_lines) = self.moves.split_at_mut(ply + 1);
+        let parent_line = &mut parent_lines[ply];
+
+        parent_line[0] = best_move;
+        if child_length > 0 {
+            parent_line[1..=child_length].copy_from_slice(&child_lines[0][..child_length]);
+        }
+
+        self.lengths[ply] = child_length + 1;
+    }
+
+    pub fn line(&self, ply: u8) -> &[MovePly] {
+        let ply = ply as usize;
+        &self.moves[ply][..self.line_length(ply)]
+    }
+
+    fn line_length(&self, ply: usize) -> usize {
+        if ply >= MAX_DEPTH {
+            return 0;
+        }
+
+        self.lengths[ply].min(MAX_DEPTH - ply - 1)
+    }
+}
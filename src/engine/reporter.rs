@@ -0,0 +1,279 @@
+use crate::chess::board::Board;
+use crate::chess::move_ply::MovePly;
+use crate::engine::info::{Bound, IterationInfo};
+use crate::engine::score;
+use crate::engine::thread_pool::ThreadResult;
+use std::sync::Mutex;
+
+// where the search sends everything it has to say, only the main thread ever reports
+// every event does nothing by default so a front-end only picks the ones it shows
+pub trait SearchReporter: Send + Sync {
+    // a finished line of a completed iteration
+    fn iteration(&self, _board: &Board, _info: &IterationInfo) {}
+
+    // an aspiration search that failed high or low and is about to be searched again
+    fn bound_update(&self, _board: &Board, _info: &IterationInfo) {}
+
+    fn current_move(&self, _depth: u8, _cur_move: &MovePly, _move_number: usize) {}
+
+    // sent every so often on long searches so the gui doesn't look frozen
+    fn progress(&self, _nodes: u64, _nps: u128, _time: u128, _hash_full: u32) {}
+
+    fn best_move(&self, _board: &Board, _result: &ThreadResult) {}
+
+    fn perft_move(&self, _cur_move: &MovePly, _nodes: u64) {}
+
+    fn perft_finished(&self, _nodes: u64, _nodes_per_second: f64, _elapsed: f64) {}
+}
+
+pub fn pv_line(pv: &[MovePly]) -> String {
+    pv.iter()
+        .map(|pv_move| pv_move.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub struct UciReporter {
+    pub show_wdl: bool,
+}
+
+impl UciReporter {
+    fn print_info(&self, info: &IterationInfo) {
+        let multi_pv = if info.multi_pv > 1 {
+            format!(" multipv {}", info.pv_idx + 1)
+        } else {
+            String::new()
+        };
+
+        let wdl = if self.show_wdl {
+            let (win, draw, loss) = info.wdl;
+            format!(" wdl {win} {draw} {loss}")
+        } else {
+            String::new()
+        };
+
+        let bound = match info.bound {
+            Bound::Exact => "",
+            Bound::Lower => " lowerbound",
+            Bound::Upper => " upperbound",
+        };
+
+        let IterationInfo {
+            depth,
+            sel_depth,
+            nodes,
+            nps,
+            time,
+            hash_full,
+            ..
+        } = info;
+        let score = score::to_uci(info.score);
+        let pv_line = pv_line(info.pv);
+
+        println!(
+            "info depth {depth} seldepth {sel_depth}{multi_pv} score {score}{wdl}{bound} nodes {nodes} nps {nps} time {time} hashfull {hash_full} pv {pv_line}"
+        );
+    }
+}
+
+impl SearchReporter for UciReporter {
+    fn iteration(&self, _board: &Board, info: &IterationInfo) {
+        self.print_info(info);
+    }
+
+    fn bound_update(&self, _board: &Board, info: &IterationInfo) {
+        self.print_info(info);
+    }
+
+    fn current_move(&self, depth: u8, cur_move: &MovePly, move_number: usize) {
+        println!("info depth {depth} currmove {cur_move} currmovenumber {move_number}");
+    }
+
+    fn progress(&self, nodes: u64, nps: u128, time: u128, hash_full: u32) {
+        println!("info nodes {nodes} nps {nps} time {time} hashfull {hash_full}");
+    }
+
+    fn best_move(&self, _board: &Board, result: &ThreadResult) {
+        let best_move = result.best_move;
+
+        match result.ponder_move {
+            Some(ponder_move) => println!("bestmove {best_move} ponder {ponder_move}\n"),
+            None => println!("bestmove {best_move}\n"),
+        }
+    }
+
+    fn perft_move(&self, cur_move: &MovePly, nodes: u64) {
+        println!("{cur_move}: {nodes}");
+    }
+
+    fn perft_finished(&self, nodes: u64, nodes_per_second: f64, elapsed: f64) {
+        println!("\nNodes searched: {nodes}");
+        println!("Nodes per second: {nodes_per_second:.0}");
+        println!("Seconds elapsed: {elapsed:.3}\n");
+    }
+}
+
+// for datagen, benchmarks and anything else that only wants the result
+pub struct SilentReporter;
+
+impl SearchReporter for SilentReporter {}
+
+#[derive(Clone, PartialEq)]
+pub enum ReportedEvent {
+    Iteration {
+        pv_idx: usize,
+        depth: u8,
+        score: i16,
+        pv: Vec<MovePly>,
+    },
+    BoundUpdate {
+        pv_idx: usize,
+        depth: u8,
+        score: i16,
+        bound: Bound,
+    },
+    CurrentMove {
+        depth: u8,
+        cur_move: MovePly,
+        move_number: usize,
+    },
+    BestMove {
+        best_move: MovePly,
+        ponder_move: Option<MovePly>,
+    },
+    PerftMove {
+        cur_move: MovePly,
+        nodes: u64,
+    },
+}
+
+// keeps every event in order so tests can look at what a search said
+#[derive(Default)]
+pub struct CollectingReporter {
+    events: Mutex<Vec<ReportedEvent>>,
+}
+
+impl CollectingReporter {
+    fn push(&self, event: ReportedEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    pub fn events(&self) -> Vec<ReportedEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl SearchReporter for CollectingReporter {
+    fn iteration(&self, _board: &Board, info: &IterationInfo) {
+        self.push(ReportedEvent::Iteration {
+            pv_idx: info.pv_idx,
+            depth: info.depth,
+            score: info.score,
+            pv: info.pv.to_vec(),
+        });
+    }
+
+    fn bound_update(&self, _board: &Board, info: &IterationInfo) {
+        self.push(ReportedEvent::BoundUpdate {
+            pv_idx: info.pv_idx,
+            depth: info.depth,
+            score: info.score,
+            bound: info.bound,
+        });
+    }
+
+    fn current_move(&self, depth: u8, cur_move: &MovePly, move_number: usize) {
+        self.push(ReportedEvent::CurrentMove {
+            depth,
+            cur_move: *cur_move,
+            move_number,
+        });
+    }
+
+    fn best_move(&self, _board: &Board, result: &ThreadResult) {
+        self.push(ReportedEvent::BestMove {
+            best_move: result.best_move,
+            ponder_move: result.ponder_move,
+        });
+    }
+
+    fn perft_move(&self, cur_move: &MovePly, nodes: u64) {
+        self.push(ReportedEvent::PerftMove {
+            cur_move: *cur_move,
+            nodes,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::perft::{PERFT, perft};
+    use crate::engine::search_limits::SearchLimits;
+    use crate::engine::skill::Skill;
+    use crate::engine::thread_pool::ThreadPool;
+    use crate::engine::time_manager::TimeManager;
+    use crate::engine::transposition::Transposition;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_search_reports_iterations_then_best_move() {
+        let mut board = Board::default();
+        board.new("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+
+        let tt = Arc::new(Transposition::new(16, 1));
+        let thread_pool = ThreadPool::new(1);
+        let search_limits =
+            SearchLimits::new(TimeManager::infinite(), 1, Skill::default()).with_max_depth(4);
+        let reporter = Arc::new(CollectingReporter::default());
+        let search_reporter: Arc<dyn SearchReporter> = reporter.clone();
+
+        let best_move = thread_pool.search_start(&tt, &board, &search_limits, &search_reporter);
+        let events = reporter.events();
+
+        let depths: Vec<u8> = events
+            .iter()
+            .filter_map(|event| match event {
+                ReportedEvent::Iteration { depth, .. } => Some(*depth),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+
+        let Some(ReportedEvent::Iteration { pv, .. }) = events
+            .iter()
+            .rfind(|event| matches!(event, ReportedEvent::Iteration { .. }))
+        else {
+            unreachable!();
+        };
+        assert_eq!(best_move.to_string(), "a1a8");
+        assert!(pv.first() == Some(&best_move));
+
+        assert!(matches!(
+            events.last(),
+            Some(ReportedEvent::BestMove { best_move: reported, .. }) if *reported == best_move
+        ));
+    }
+
+    #[test]
+    fn test_perft_reports_every_root_move() {
+        let mut board = Board::default();
+        board.new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let reporter = CollectingReporter::default();
+        let nodes = perft::<PERFT>(&mut board, 3, &reporter);
+
+        let events = reporter.events();
+        assert_eq!(events.len(), 20);
+
+        let reported_nodes: u64 = events
+            .iter()
+            .map(|event| match event {
+                ReportedEvent::PerftMove { nodes, .. } => *nodes,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(reported_nodes, nodes);
+        assert_eq!(nodes, 8_902);
+    }
+}
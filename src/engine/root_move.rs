@@ -0,0 +1,27 @@
+use crate::chess::move_ply::MovePly;
+use crate::engine::score::INFINITY;
+
+// scores of moves that were not good enough to raise alpha are meaningless,
+// so they are pushed to the back when the root moves are sorted
+pub const UNSEARCHED_SCORE: i16 = -INFINITY;
+
+#[derive(Clone)]
+pub struct RootMove {
+    pub cur_move: MovePly,
+    pub score: i16,
+    pub previous_score: i16,
+    pub pv: Vec<MovePly>,
+    pub nodes: u64,
+}
+
+impl RootMove {
+    pub fn new(cur_move: MovePly) -> Self {
+        Self {
+            cur_move,
+            score: UNSEARCHED_SCORE,
+            previous_score: UNSEARCHED_SCORE,
+            pv: vec![cur_move],
+            nodes: 0,
+        }
+    }
+}
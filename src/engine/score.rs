@@ -0,0 +1,81 @@
+use crate::chess::consts::MAX_DEPTH;
+use crate::engine::wdl;
+
+// every score the search can return lies strictly between -INFINITY and INFINITY
+pub const INFINITY: i16 = 32000;
+
+// mate scores count down from MATE by the number of plies it takes to deliver mate
+pub const MATE: i16 = 30000;
+pub const MATE_IN_MAX_PLY: i16 = MATE - MAX_DEPTH as i16;
+
+// tablebase wins sit just below the mates so a real mate is always preferred
+pub const TB_WIN: i16 = MATE_IN_MAX_PLY - 1;
+pub const TB_WIN_IN_MAX_PLY: i16 = TB_WIN - MAX_DEPTH as i16;
+
+pub fn mate_in(ply: u8) -> i16 {
+    MATE - ply as i16
+}
+
+pub fn mated_in(ply: u8) -> i16 {
+    -MATE + ply as i16
+}
+
+// mates and tablebase results, scores that don't come from the eval
+pub fn is_decisive(score: i16) -> bool {
+    score.abs() >= TB_WIN_IN_MAX_PLY
+}
+
+// the tt stores decisive scores relative to the position they were found in rather than the root,
+// so the same entry is valid no matter at which ply the position is reached
+pub fn to_tt(score: i16, ply: u8) -> i16 {
+    if score >= TB_WIN_IN_MAX_PLY {
+        score + ply as i16
+    } else if score <= -TB_WIN_IN_MAX_PLY {
+        score - ply as i16
+    } else {
+        score
+    }
+}
+
+pub fn from_tt(score: i16, ply: u8) -> i16 {
+    if score >= TB_WIN_IN_MAX_PLY {
+        score - ply as i16
+    } else if score <= -TB_WIN_IN_MAX_PLY {
+        score + ply as i16
+    } else {
+        score
+    }
+}
+
+// what the protocols print: mates in full moves, negative when we are the ones getting mated,
+// and everything else in centipawns on the normalized scale
+pub enum ReportedScore {
+    Cp(i16),
+    Mate(i16),
+}
+
+pub fn to_reported(score: i16) -> ReportedScore {
+    if score >= MATE_IN_MAX_PLY {
+        ReportedScore::Mate((MATE - score + 1) / 2)
+    } else if score <= -MATE_IN_MAX_PLY {
+        ReportedScore::Mate(-(MATE + score) / 2)
+    } else {
+        ReportedScore::Cp(wdl::normalize(score))
+    }
+}
+
+pub fn to_uci(score: i16) -> String {
+    match to_reported(score) {
+        ReportedScore::Cp(cp) => format!("cp {cp}"),
+        ReportedScore::Mate(moves) => format!("mate {moves}"),
+    }
+}
+
+// xboard shows mates as 100000 plus the number of moves, the same scale as cecp engines use
+pub fn to_xboard(score: i16) -> i32 {
+    match to_reported(score) {
+        ReportedScore::Cp(cp) => cp as i32,
+        ReportedScore::Mate(moves) if moves > 0 => 100000 + moves as i32,
+        ReportedScore::Mate(moves) => -100000 + moves as i32,
+    }
+}
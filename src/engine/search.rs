@@ -7,31 +7,58 @@ use crate::chess::move_list::MoveList;
 use crate::chess::move_ply::MovePly;
 use crate::chess::types::color::Color::White;
 use crate::chess::types::piece::BasePiece::{King, Pawn};
+use crate::chess::types::piece::Piece::NoPiece;
 use crate::engine::arbiter::Arbiter;
 use crate::engine::capture_history::CaptureHeuristics;
+use crate::engine::correction_history::CorrectionHistory;
 use crate::engine::counter_move_heuristics::CounterMoveHeuristics;
 use crate::engine::eval::nnue::NNUE;
+use crate::engine::info::{Bound, IterationInfo};
+use crate::engine::pv_table::PvTable;
+use crate::engine::reporter::SearchReporter;
+use crate::engine::root_move::{RootMove, UNSEARCHED_SCORE};
+use crate::engine::score::{self, INFINITY};
 use crate::engine::search_funcs::{move_is_capture, see};
 use crate::engine::search_limits::SearchLimits;
+use crate::engine::search_stack::{NUM_KILLERS, SearchStackEntry};
+use crate::engine::thread_pool::{MAIN_THREAD, SharedSearchState, ThreadResult};
+use crate::engine::time_manager::IterationReport;
 use crate::engine::transposition::{TTEntry, Transposition};
 use crate::engine::types::match_result::MatchResult;
 use crate::engine::types::tt_flag::TTFlag;
+use crate::engine::wdl;
 use crate::precomputed::accessor::LMR_REDUCTION;
+use std::cmp::Reverse;
 use std::sync::Arc;
-use std::sync::atomic::Ordering;
-use std::thread;
 
-const INFINITY: i16 = 30000;
+// long searches report what the main thread is up to so the gui doesn't look frozen
+const CURRMOVE_DELAY_MS: u128 = 3000;
+const HEARTBEAT_INTERVAL_MS: u128 = 1000;
+const HEARTBEAT_NODE_INTERVAL: u128 = 4096;
 
 pub struct Searcher {
     board: Board,
-    search_stack: [MovePly; consts::MAX_DEPTH],
+    search_stack: [SearchStackEntry; consts::MAX_DEPTH],
+    pv_table: Box<PvTable>,
     capture_heuristics: CaptureHeuristics,
     counter_move_heuristics: CounterMoveHeuristics,
+    correction_history: Box<CorrectionHistory>,
     nodes: u128,
+    sel_depth: u8,
+    last_heartbeat: u128,
     tt: Arc<Transposition>,
     nnue: NNUE,
     search_limits: SearchLimits,
+    reporter: Arc<dyn SearchReporter>,
+    thread_id: usize,
+    shared: Arc<SharedSearchState>,
+    root_moves: Vec<RootMove>,
+    pv_idx: usize,
+    best_move: MovePly,
+    ponder_move: Option<MovePly>,
+    best_score: i16,
+    completed_depth: u8,
+    best_move_stability: usize,
 }
 
 const IS_ROOT: bool = true;
@@ -42,27 +69,186 @@ impl Searcher {
         transposition: &Arc<Transposition>,
         board: &Board,
         search_limits: &SearchLimits,
+        reporter: &Arc<dyn SearchReporter>,
+        thread_id: usize,
+        shared: &Arc<SharedSearchState>,
     ) -> Self {
         Self {
             board: board.clone(),
-            search_stack: [MovePly::default(); consts::MAX_DEPTH],
+            search_stack: [SearchStackEntry::default(); consts::MAX_DEPTH],
+            pv_table: Box::default(),
             capture_heuristics: CaptureHeuristics::default(),
             counter_move_heuristics: CounterMoveHeuristics::default(),
+            correction_history: Box::default(),
             nodes: 0,
+            sel_depth: 0,
+            last_heartbeat: 0,
             tt: Arc::clone(transposition),
-            nnue: NNUE::new(board.clone()),
+            nnue: NNUE::new(board),
             search_limits: search_limits.clone(),
+            reporter: Arc::clone(reporter),
+            thread_id,
+            shared: Arc::clone(shared),
+            root_moves: Self::generate_root_moves(board),
+            pv_idx: 0,
+            best_move: MovePly::default(),
+            ponder_move: None,
+            best_score: 0,
+            completed_depth: 0,
+            best_move_stability: 0,
         }
     }
 
-    fn adjust_mate_distance(mut eval: i16, ply: u8) -> i16 {
-        if eval > 29000 {
-            eval -= ply as i16;
-        } else if eval < -29000 {
-            eval += ply as i16;
+    fn generate_root_moves(board: &Board) -> Vec<RootMove> {
+        MoveGenerator::<GEN_ALL>::iter(board)
+            .map(RootMove::new)
+            .collect()
+    }
+
+    // the heuristics carry over from the last search of the same game, everything else starts fresh
+    pub fn prepare(
+        &mut self,
+        transposition: &Arc<Transposition>,
+        board: &Board,
+        search_limits: &SearchLimits,
+        reporter: &Arc<dyn SearchReporter>,
+    ) {
+        self.board = board.clone();
+        self.search_stack = [SearchStackEntry::default(); consts::MAX_DEPTH];
+        self.nodes = 0;
+        self.sel_depth = 0;
+        self.last_heartbeat = 0;
+        self.tt = Arc::clone(transposition);
+        self.nnue = NNUE::new(board);
+        self.search_limits = *search_limits;
+        self.reporter = Arc::clone(reporter);
+        self.root_moves = Self::generate_root_moves(board);
+        self.pv_idx = 0;
+        self.best_move = MovePly::default();
+        self.ponder_move = None;
+        self.best_score = 0;
+        self.completed_depth = 0;
+        self.best_move_stability = 0;
+
+        if self.search_limits.history_decay() {
+            self.capture_heuristics.decay();
+        }
+    }
+
+    pub fn clear_heuristics(&mut self) {
+        self.capture_heuristics = CaptureHeuristics::default();
+        self.counter_move_heuristics = CounterMoveHeuristics::default();
+        *self.correction_history = CorrectionHistory::default();
+    }
+
+    // helpers only stop once the main thread is done, so they never cut it short
+    // a node limit only kicks in after the first iteration so there is always a searched move to play
+    fn should_stop(&self) -> bool {
+        self.search_limits.is_hard_stop()
+            || self.shared.is_stopped()
+            || (self.completed_depth > 0 && self.search_limits.is_node_limit_reached(self.nodes))
+    }
+
+    fn count_node(&mut self, ply: u8) {
+        self.nodes += 1;
+        self.sel_depth = self.sel_depth.max(ply);
+        self.shared.set_nodes(self.thread_id, self.nodes as u64);
+
+        if self.thread_id == MAIN_THREAD && self.nodes.is_multiple_of(HEARTBEAT_NODE_INTERVAL) {
+            self.heartbeat();
+        }
+    }
+
+    fn heartbeat(&mut self) {
+        let time = self.search_limits.ms_elapsed();
+        if time < self.last_heartbeat + HEARTBEAT_INTERVAL_MS {
+            return;
+        }
+
+        self.last_heartbeat = time;
+
+        let nodes = self.shared.total_nodes();
+        let nps = (nodes as f64 / (time as f64 / 1000f64).max(0.0001f64)) as u128;
+        let hash_full = self.tt.hash_full();
+
+        self.reporter.progress(nodes, nps, time, hash_full);
+    }
+
+    fn report_info(&self, pv_idx: usize, depth: u8, score: i16, bound: Bound) {
+        let time = self.search_limits.ms_elapsed();
+        let nodes = self.shared.total_nodes();
+
+        let info = IterationInfo {
+            pv_idx,
+            multi_pv: self.search_limits.multi_pv(),
+            depth,
+            sel_depth: self.sel_depth.max(depth),
+            score,
+            bound,
+            wdl: wdl::wdl(score, wdl::material(&self.board)),
+            nodes,
+            nps: (nodes as f64 / (time as f64 / 1000f64).max(0.0001f64)) as u128,
+            time,
+            hash_full: self.tt.hash_full(),
+            pv: &self.root_moves[pv_idx].pv,
+        };
+
+        match bound {
+            Bound::Exact => self.reporter.iteration(&self.board, &info),
+            Bound::Lower | Bound::Upper => self.reporter.bound_update(&self.board, &info),
         }
+    }
 
-        eval
+    // the uncorrected eval is what gets stored in the transposition table
+    // since the corrections keep changing as the search goes on
+    fn raw_eval(&self, tt_entry: &Option<TTEntry>) -> i16 {
+        match tt_entry {
+            Some(entry) => entry.static_eval,
+            None => self.nnue.evaluate(self.board.side_to_move()),
+        }
+    }
+
+    // a tt score is a better guess than the static eval as long as its bound agrees with it
+    fn tt_adjusted_eval(tt_entry: &Option<TTEntry>, static_eval: i16, ply: u8) -> i16 {
+        let Some(entry) = tt_entry else {
+            return static_eval;
+        };
+
+        let tt_eval = score::from_tt(entry.eval, ply);
+        let bound_allows = match entry.tt_flag() {
+            TTFlag::Exact => true,
+            TTFlag::Lower => tt_eval > static_eval,
+            TTFlag::Upper => tt_eval < static_eval,
+        };
+
+        if bound_allows { tt_eval } else { static_eval }
+    }
+
+    fn update_correction_history(
+        &mut self,
+        depth: u8,
+        best_move: MovePly,
+        score: i16,
+        static_eval: i16,
+        tt_flag: TTFlag,
+    ) {
+        let is_tactical =
+            move_is_capture(&self.board, &best_move) || best_move.flag().is_promotion();
+        let is_mate = score::is_decisive(score);
+
+        // the score has to be on the correct side of the static eval for its bound to say anything
+        let bound_allows = match tt_flag {
+            TTFlag::Exact => true,
+            TTFlag::Lower => score > static_eval,
+            TTFlag::Upper => score < static_eval,
+        };
+
+        if is_tactical || is_mate || !bound_allows {
+            return;
+        }
+
+        self.correction_history
+            .update(&self.board, depth, score, static_eval);
     }
 
     fn search<const ROOT: bool>(
@@ -70,93 +256,87 @@ impl Searcher {
         ply: u8,
         mut depth: u8,
         mut alpha: i16,
-        beta: i16,
+        mut beta: i16,
     ) -> i16 {
-        if self.search_limits.is_hard_stop() {
+        if self.should_stop() {
             return 0;
         }
 
-        // let mating_value = INFINITY - ply as i16;
-        // if mating_value < beta {
-        //     beta = mating_value;
-        //     if alpha >= beta {
-        //         return beta;
-        //     }
-        // }
+        self.pv_table.clear(ply);
 
-        // let mated_value = -INFINITY + ply as i16;
-        // if mated_value > alpha {
-        //     alpha = mated_value;
-        //     if alpha >= beta {
-        //         return alpha;
-        //     }
-        // }
+        let pv_node = alpha != beta - 1;
 
-        if self.board.in_check() {
-            depth += 1;
+        // mate distance pruning, no line from here can beat a mate we already found closer to the root
+        if !ROOT {
+            alpha = alpha.max(score::mated_in(ply));
+            beta = beta.min(score::mate_in(ply + 1));
+
+            if alpha >= beta {
+                return alpha;
+            }
         }
 
-        let mut move_list = MoveList::default();
-        MoveGenerator::<GEN_ALL>::generate(&mut self.board, &mut |mut piece_moves| {
-            move_list.add_piece_moves(&mut piece_moves);
-        });
+        // a node in check gets an extra ply, so it never drops into qsearch and stands pat there
+        let in_check = self.board.in_check();
+        if in_check {
+            depth += 1;
+        }
 
-        let match_result = Arbiter::arbitrate(&mut self.board, &move_list);
-        match match_result {
-            MatchResult::Draw => return -50,
-            MatchResult::Loss => return -INFINITY + ply as i16,
-            MatchResult::NoResult => {}
+        // checkmate and stalemate have to wait for the moves, the rest of the rules don't
+        if Arbiter::is_rule_draw(&self.board) {
+            return -50;
         }
 
         if depth == 0 {
             return self.quiescence_search(ply, 8, alpha, beta);
         }
 
-        self.nodes += 1;
+        self.count_node(ply);
+
+        // later multipv lines only search part of the root, so what they find says nothing about the position
+        let full_node = !ROOT || self.pv_idx == 0;
+        self.search_stack[ply as usize].in_check = in_check;
+
+        // the children start with a clean slate, killers from a sibling subtree are about other positions
+        self.search_stack[ply as usize + 1].killers = [MovePly::default(); NUM_KILLERS];
 
         let tt_entry = self.tt.probe(self.board.zobrist());
         if let Some(entry) = tt_entry
             && entry.depth >= depth
             && !ROOT
         {
-            match entry.tt_flag {
+            // mate scores are stored relative to the node, so they have to be brought back first
+            let tt_score = score::from_tt(entry.eval, ply);
+
+            match entry.tt_flag() {
                 TTFlag::Exact => {
                     if self.board.is_repetition() {
                         return -50;
                     }
 
-                    if ply == 0 {
-                        self.tt
-                            .best_move
-                            .store(entry.cur_move.packed_data(), Ordering::Relaxed);
-                        self.tt.best_move_score.store(entry.eval, Ordering::Relaxed);
-                    }
-
-                    return Self::adjust_mate_distance(entry.eval, ply);
+                    return tt_score;
                 }
                 TTFlag::Upper => {
-                    if entry.eval <= alpha {
+                    if tt_score <= alpha {
                         if self.board.is_repetition() {
                             return -50;
                         }
 
-                        return Self::adjust_mate_distance(entry.eval, ply);
+                        return tt_score;
                     }
                 }
                 TTFlag::Lower => {
-                    if entry.eval >= beta {
+                    if tt_score >= beta {
                         if self.board.is_repetition() {
                             return -50;
                         }
 
-                        return Self::adjust_mate_distance(entry.eval, ply);
+                        return tt_score;
                     }
                 }
             }
         }
 
-        let pv_node = alpha != beta - 1;
-
         let last_move_was_null = {
             if let Some(last_move) = self.board.last_move() {
                 last_move.is_default()
@@ -165,13 +345,27 @@ impl Searcher {
             }
         };
 
-        if !self.board.in_check()
+        let raw_eval = self.raw_eval(&tt_entry);
+        let static_eval = self.correction_history.correct(&self.board, raw_eval);
+        self.search_stack[ply as usize].static_eval = static_eval;
+        let eval = Self::tt_adjusted_eval(&tt_entry, static_eval, ply);
+
+        // the position is getting better for us compared to our last move
+        let improving = !in_check
+            && ply >= 2
+            && !self.search_stack[ply as usize - 2].in_check
+            && static_eval > self.search_stack[ply as usize - 2].static_eval;
+
+        if !in_check
             && depth >= 3
             && !Self::in_zugzwang(&self.board)
             && !last_move_was_null
             && !pv_node
         {
+            self.search_stack[ply as usize].cur_move = MovePly::default();
+            self.search_stack[ply as usize].moved_piece = NoPiece;
             self.board.make_null_move();
+            self.tt.prefetch(self.board.zobrist());
 
             // let reduction = 3;
 
@@ -182,7 +376,7 @@ impl Searcher {
 
             let score = -self.search::<NOT_ROOT>(ply + 1, new_depth, -beta, -beta + 1);
 
-            if self.search_limits.is_hard_stop() {
+            if self.should_stop() {
                 return 0;
             }
 
@@ -194,13 +388,9 @@ impl Searcher {
         }
 
         // reverse futility pruning
-        let static_eval = self.nnue.evaluate(self.board.side_to_move());
+        let futility_depth = depth as i16 - improving as i16;
 
-        if !pv_node
-            && !self.board.in_check()
-            && static_eval >= (beta + 100 * depth as i16)
-            && depth < 9
-        {
+        if !pv_node && !in_check && eval >= (beta + 100 * futility_depth) && depth < 9 {
             return beta;
         }
 
@@ -209,7 +399,20 @@ impl Searcher {
             depth -= 1;
         }
 
-        self.order_moves(&mut move_list, &tt_entry);
+        // moves are only generated once nothing above could cut the node short
+        let mut move_list = MoveList::default();
+        MoveGenerator::<GEN_ALL>::fill(&self.board, &mut move_list);
+
+        if move_list.move_count() == 0 {
+            return if in_check { score::mated_in(ply) } else { -50 };
+        }
+
+        self.order_moves(&mut move_list, &tt_entry, ply);
+
+        // once an iteration is done the root moves know better than any heuristic
+        if ROOT && self.completed_depth > 0 {
+            self.order_root_moves(&mut move_list);
+        }
 
         let mut capture_moves = Vec::with_capacity(20);
         let mut quiet_moves = Vec::with_capacity(20);
@@ -217,8 +420,18 @@ impl Searcher {
         let mut node_type = TTFlag::Upper;
         let mut best_eval = -INFINITY;
         let mut best_move = move_list.move_at(0);
+        let mut root_moves_searched = 0;
 
         for (move_count, cur_move) in move_list.iter().enumerate() {
+            // moves that already have their own multipv line are left out of the later ones
+            if ROOT
+                && self.root_moves[..self.pv_idx]
+                    .iter()
+                    .any(|root_move| root_move.cur_move == *cur_move)
+            {
+                continue;
+            }
+
             let is_capture = move_is_capture(&self.board, cur_move);
             // let reduction = LMR_REDUCTION.reduction(depth, move_count as u8);
             // let lmr_depth = (depth as i16 - reduction as i16 - 1).max(0) as u8;
@@ -235,15 +448,26 @@ impl Searcher {
             //     continue;
             // }
 
+            let stack_entry = &mut self.search_stack[ply as usize];
+            stack_entry.cur_move = *cur_move;
+            stack_entry.moved_piece = self.board.piece_at(cur_move.from());
+
+            let nodes_before = self.nodes;
+
+            if ROOT
+                && self.thread_id == MAIN_THREAD
+                && self.search_limits.ms_elapsed() >= CURRMOVE_DELAY_MS
+            {
+                self.reporter.current_move(depth, cur_move, move_count + 1);
+            }
+
             self.nnue.make_move(cur_move, &self.board);
             self.board.make_move::<true>(cur_move);
-
-            self.search_stack[ply as usize] = *cur_move;
+            self.tt.prefetch(self.board.zobrist());
 
             let mut eval;
 
-            let should_reduce =
-                depth >= 3 && move_count >= 4 && !is_capture && !self.board.in_check();
+            let should_reduce = depth >= 3 && move_count >= 4 && !is_capture && !in_check;
 
             if should_reduce {
                 let reduction = LMR_REDUCTION.reduction(depth, move_count as u8);
@@ -267,23 +491,59 @@ impl Searcher {
                 }
             }
 
-            if self.search_limits.is_hard_stop() {
+            if self.should_stop() {
                 return 0;
             }
 
             self.board.undo_move();
             self.nnue.undo_move();
 
+            if ROOT {
+                let root_move = self
+                    .root_moves
+                    .iter_mut()
+                    .find(|root_move| root_move.cur_move == *cur_move)
+                    .unwrap();
+
+                root_move.nodes += (self.nodes - nodes_before) as u64;
+
+                // the first move always keeps its score, even as a bound,
+                // so a fail low doesn't throw away which move was best
+                root_move.score = if root_moves_searched == 0 || eval > alpha {
+                    eval
+                } else {
+                    UNSEARCHED_SCORE
+                };
+                root_moves_searched += 1;
+
+                if eval > alpha {
+                    root_move.pv.clear();
+                    root_move.pv.push(*cur_move);
+                    root_move.pv.extend_from_slice(self.pv_table.line(ply + 1));
+                }
+            }
+
             if eval >= beta {
-                self.tt.update(
-                    self.board.zobrist(),
-                    *cur_move,
-                    beta,
-                    depth,
-                    TTFlag::Lower,
-                    false,
-                    ply,
-                );
+                if full_node {
+                    self.tt.update(
+                        self.board.zobrist(),
+                        TTEntry::new(*cur_move, beta, raw_eval, depth, TTFlag::Lower, ply),
+                    );
+                }
+
+                if !is_capture && !cur_move.flag().is_promotion() {
+                    self.search_stack[ply as usize].update_killers(*cur_move);
+                }
+
+                if !in_check && full_node {
+                    self.update_correction_history(
+                        depth,
+                        *cur_move,
+                        beta,
+                        static_eval,
+                        TTFlag::Lower,
+                    );
+                }
 
                 self.capture_heuristics
                     .update(&self.board, &cur_move, &capture_moves, depth);
@@ -311,6 +571,7 @@ impl Searcher {
                 alpha = eval;
                 node_type = TTFlag::Exact;
                 best_move = *cur_move;
+                self.pv_table.update(ply, *cur_move);
             }
 
             if eval > best_eval {
@@ -325,21 +586,15 @@ impl Searcher {
             }
         }
 
-        self.tt.update(
-            self.board.zobrist(),
-            best_move,
-            alpha,
-            depth,
-            node_type,
-            pv_node,
-            ply,
-        );
+        if full_node {
+            self.tt.update(
+                self.board.zobrist(),
+                TTEntry::new(best_move, alpha, raw_eval, depth, node_type, ply),
+            );
+        }
 
-        if ply == 0 {
-            self.tt
-                .best_move
-                .store(best_move.packed_data(), Ordering::Relaxed);
-            self.tt.best_move_score.store(alpha, Ordering::Relaxed);
+        if !in_check && full_node {
+            self.update_correction_history(depth, best_move, alpha, static_eval, node_type);
         }
 
         alpha
@@ -355,33 +610,39 @@ impl Searcher {
     }
 
     fn quiescence_search(&mut self, ply: u8, depth: u8, mut alpha: i16, beta: i16) -> i16 {
-        if self.search_limits.is_hard_stop() {
+        if self.should_stop() {
             return 0;
         }
 
-        self.nodes += 1;
+        self.pv_table.clear(ply);
+        self.count_node(ply);
         let pv_node = alpha != beta - 1;
 
         let tt_entry = self.tt.probe(self.board.zobrist());
         if let Some(entry) = tt_entry {
             if !pv_node && entry.depth >= depth {
-                match entry.tt_flag {
-                    TTFlag::Exact => return Self::adjust_mate_distance(entry.eval, ply),
+                let tt_score = score::from_tt(entry.eval, ply);
+
+                match entry.tt_flag() {
+                    TTFlag::Exact => return tt_score,
                     TTFlag::Upper => {
-                        if entry.eval <= alpha {
-                            return Self::adjust_mate_distance(entry.eval, ply);
+                        if tt_score <= alpha {
+                            return tt_score;
                         }
                     }
                     TTFlag::Lower => {
-                        if entry.eval >= beta {
-                            return Self::adjust_mate_distance(entry.eval, ply);
+                        if tt_score >= beta {
+                            return tt_score;
                         }
                     }
                 }
             }
         }
 
-        let eval = self.nnue.evaluate(self.board.side_to_move());
+        let raw_eval = self.raw_eval(&tt_entry);
+        let static_eval = self.correction_history.correct(&self.board, raw_eval);
+        self.search_stack[ply as usize].static_eval = static_eval;
+        let eval = Self::tt_adjusted_eval(&tt_entry, static_eval, ply);
 
         if eval >= beta {
             return beta;
@@ -395,31 +656,34 @@ impl Searcher {
         }
 
         let mut all_move_list = MoveList::default();
-        MoveGenerator::<GEN_ALL>::generate(&mut self.board, &mut |mut piece_moves| {
-            all_move_list.add_piece_moves(&mut piece_moves);
-        });
+        MoveGenerator::<GEN_ALL>::fill(&self.board, &mut all_move_list);
 
         let match_result = Arbiter::arbitrate(&self.board, &all_move_list);
         match match_result {
             MatchResult::Draw => return -50,
-            MatchResult::Loss => return -INFINITY + ply as i16,
+            MatchResult::Loss => return score::mated_in(ply),
             MatchResult::NoResult => {}
         }
 
+        self.search_stack[ply as usize].in_check = self.board.in_check();
+
         let mut tactial_move_list = MoveList::default();
-        MoveGenerator::<GEN_TACTICS>::generate(&mut self.board, &mut |mut piece_moves| {
-            tactial_move_list.add_piece_moves(&mut piece_moves);
-        });
+        MoveGenerator::<GEN_TACTICS>::fill(&self.board, &mut tactial_move_list);
 
         let mut node_type = TTFlag::Upper;
         let mut best_move = tactial_move_list.move_at(0);
 
         for cur_move in tactial_move_list.iter() {
+            let stack_entry = &mut self.search_stack[ply as usize];
+            stack_entry.cur_move = *cur_move;
+            stack_entry.moved_piece = self.board.piece_at(cur_move.from());
+
             self.nnue.make_move(cur_move, &mut self.board);
             self.board.make_move::<true>(cur_move);
+            self.tt.prefetch(self.board.zobrist());
 
             let eval = -self.quiescence_search(ply + 1, depth - 1, -beta, -alpha);
-            if self.search_limits.is_hard_stop() {
+            if self.should_stop() {
                 return 0;
             }
 
@@ -429,12 +693,7 @@ impl Searcher {
             if eval >= beta {
                 self.tt.update(
                     self.board.zobrist(),
-                    *cur_move,
-                    alpha,
-                    0,
-                    TTFlag::Lower,
-                    false,
-                    ply,
+                    TTEntry::new(*cur_move, alpha, raw_eval, 0, TTFlag::Lower, ply),
                 );
                 return beta;
             }
@@ -443,82 +702,58 @@ impl Searcher {
                 best_move = *cur_move;
                 node_type = TTFlag::Exact;
                 alpha = eval;
+                self.pv_table.update(ply, *cur_move);
             }
         }
 
         self.tt.update(
             self.board.zobrist(),
-            best_move,
-            alpha,
-            0,
-            node_type,
-            false,
-            ply,
+            TTEntry::new(best_move, alpha, raw_eval, 0, node_type, ply),
         );
 
         alpha
     }
 
-    fn pv_from_transposition(&self) -> String {
-        let mut board_clone = self.board.clone();
-        let mut pv_line = String::new();
-
-        loop {
-            if pv_line.chars().filter(|&cur_char| ' ' == cur_char).count() == 20 {
-                break;
-            }
-
-            if let Some(entry) = self.tt.probe(board_clone.zobrist()) {
-                let best_move = entry.cur_move;
-
-                let mut valid_moves = MoveList::default();
-                MoveGenerator::<GEN_ALL>::generate(&mut board_clone, &mut |mut piece_moves| {
-                    valid_moves.add_piece_moves(&mut piece_moves);
-                });
-
-                if !valid_moves.contains_move(best_move) {
-                    break;
-                }
-
-                let match_result = Arbiter::arbitrate(&board_clone, &valid_moves);
-
-                if !matches!(match_result, MatchResult::NoResult) || board_clone.is_repetition() {
-                    break;
-                }
-
-                pv_line += &format!("{best_move} ");
-                board_clone.make_move::<true>(&best_move);
-            } else {
-                break;
-            }
-        }
-
-        pv_line
-    }
-
     fn aspiration_windows(&mut self, depth: u8) {
         let mut alpha = -INFINITY;
         let mut beta = INFINITY;
         let mut delta = 35;
 
-        if depth >= 5 {
-            let current_eval = self.tt.best_move_score.load(Ordering::Relaxed);
-            alpha = current_eval - delta;
-            beta = current_eval + delta;
+        let current_eval = if self.pv_idx == 0 {
+            self.best_score
+        } else {
+            self.root_moves[self.pv_idx].previous_score
+        };
+
+        if depth >= 5 && current_eval != UNSEARCHED_SCORE {
+            alpha = (-INFINITY).max(current_eval - delta);
+            beta = INFINITY.min(current_eval + delta);
         }
 
         loop {
             let eval = self.search::<IS_ROOT>(0, depth, alpha, beta);
 
-            if self.search_limits.is_hard_stop() {
+            if self.should_stop() {
                 return;
             }
 
+            // stable so moves that failed to raise alpha keep their order from the last iteration
+            // and the lines that are already done stay where they are
+            self.root_moves[self.pv_idx..].sort_by_key(|root_move| Reverse(root_move.score));
+
+            if self.thread_id == MAIN_THREAD {
+                if eval <= alpha {
+                    self.report_info(self.pv_idx, depth, eval, Bound::Upper);
+                } else if eval >= beta {
+                    self.report_info(self.pv_idx, depth, eval, Bound::Lower);
+                }
+            }
+
             if eval <= alpha {
                 beta = (alpha + beta) / 2;
                 alpha = (-INFINITY).max(alpha - delta)
             } else if eval >= beta {
-                beta += delta;
+                beta = INFINITY.min(beta + delta);
             } else {
                 break;
             }
@@ -530,42 +765,116 @@ impl Searcher {
             }
         }
     }
-    pub fn iterative_deepening(&mut self) -> MovePly {
-        for cur_depth in (1..64).step_by(1) {
-            self.aspiration_windows(cur_depth);
+    pub fn iterative_deepening(&mut self) -> ThreadResult {
+        // if not even the first iteration finishes any legal move beats no move at all
+        if let Some(first_root_move) = self.root_moves.first() {
+            self.best_move = first_root_move.cur_move;
+        }
 
-            if self.tt.curr_depth.load(Ordering::SeqCst) >= cur_depth {
-                continue;
+        let multi_pv = self.search_limits.multi_pv().min(self.root_moves.len());
+        let mut skill_move = None;
+
+        'deepening: for cur_depth in 1..=self.search_limits.max_depth() {
+            for root_move in self.root_moves.iter_mut() {
+                root_move.previous_score = root_move.score;
             }
+            self.sel_depth = 0;
 
-            self.tt.curr_depth.add(1, Ordering::SeqCst);
+            for pv_idx in 0..multi_pv {
+                self.pv_idx = pv_idx;
+                self.aspiration_windows(cur_depth);
+
+                // a move from an unfinished iteration can't be trusted
+                if self.should_stop() {
+                    break 'deepening;
+                }
+            }
 
-            // self.search::<IS_ROOT>(0, cur_depth, -INFINITY, INFINITY);
-            if self.search_limits.is_hard_stop() {
+            self.completed_depth = cur_depth;
+            // mated or stalemated, there is nothing to search
+            let Some(best_root_move) = self.root_moves.first() else {
                 break;
+            };
+
+            let best_move_unchanged = best_root_move.cur_move == self.best_move;
+            self.best_move_stability = if best_move_unchanged && cur_depth > 1 {
+                self.best_move_stability + 1
+            } else {
+                0
+            };
+
+            let score_drop = if cur_depth > 1 {
+                self.best_score.saturating_sub(best_root_move.score)
+            } else {
+                0
+            };
+
+            let report = IterationReport {
+                best_move_stability: self.best_move_stability,
+                score_drop,
+                best_move_node_fraction: best_root_move.nodes as f64 / self.nodes.max(1) as f64,
+            };
+
+            self.best_move = best_root_move.cur_move;
+            self.best_score = best_root_move.score;
+            self.ponder_move = best_root_move.pv.get(1).copied();
+
+            if self.thread_id != MAIN_THREAD {
+                continue;
             }
 
-            let pv_line = self.pv_from_transposition();
-            // let sel_depth = pv_line.split(" ").collect::<Vec<&str>>().len() - 1;
-            let time = self.search_limits.ms_elapsed();
-            let eval = self.tt.best_move_score.load(Ordering::Relaxed);
-            let nodes = self.nodes;
-            let nps = (nodes as f64 / (time as f64 / 1000f64).max(0.0001f64)) as u128;
-            let hash_full = self.tt.hash_full();
+            // only the pick from the last completed iteration is played
+            let skill = self.search_limits.skill();
+            if skill.is_enabled() {
+                let picked_line = skill.pick_move(&self.root_moves[..multi_pv]);
+                skill_move = Some((picked_line.cur_move, picked_line.pv.get(1).copied()));
+            }
 
-            println!(
-                "info depth {cur_depth} score cp {eval} nodes {nodes} nps {nps} time {time} hashfull {hash_full} pv {pv_line}"
-            );
+            for pv_idx in 0..multi_pv {
+                self.report_info(
+                    pv_idx,
+                    cur_depth,
+                    self.root_moves[pv_idx].score,
+                    Bound::Exact,
+                );
+            }
 
-            if self.search_limits.is_soft_stop() {
+            if self.search_limits.is_soft_stop(&report) {
                 break;
             }
         }
 
-        self.tt.best_move.load(Ordering::Relaxed).into()
+        if let Some((best_move, ponder_move)) = skill_move {
+            self.best_move = best_move;
+            self.ponder_move = ponder_move;
+        }
+
+        ThreadResult {
+            thread_id: self.thread_id,
+            best_move: self.best_move,
+            ponder_move: self.ponder_move,
+            score: self.best_score,
+            depth: self.completed_depth,
+        }
+    }
+
+    fn order_root_moves(&self, move_list: &mut MoveList) {
+        let mut move_values: [i16; 256] = [0; 256];
+
+        for (i, cur_move) in move_list.iter().enumerate() {
+            let rank = self
+                .root_moves
+                .iter()
+                .position(|root_move| root_move.cur_move == *cur_move)
+                .unwrap();
+
+            move_values[i] = -(rank as i16);
+        }
+
+        move_list.order_moves(&move_values);
     }
 
-    fn order_moves(&mut self, move_list: &mut MoveList, prev_best_move: &Option<TTEntry>) {
+    fn order_moves(&mut self, move_list: &mut MoveList, prev_best_move: &Option<TTEntry>, ply: u8) {
         let mut move_values: [i16; 256] = [0; 256];
 
         // let counter_move = MovePly::default();
@@ -607,9 +916,9 @@ impl Searcher {
             // 8 1386
             // 1396
 
-            // if self.killers.contains(ply_searched, *cur_move) {
-            //     move_values[i] += 5000;
-            // }
+            if self.search_stack[ply as usize].is_killer(*cur_move) {
+                move_values[i] += 300;
+            }
 
             move_values[i] += 2 * see(cur_move.from(), cur_move.to(), &self.board);
 
@@ -624,43 +933,78 @@ impl Searcher {
 
         move_list.order_moves(&move_values);
     }
+}
 
-    pub fn search_start(
-        tt: &Arc<Transposition>,
-        board: &Board,
-        search_limits: &SearchLimits,
-        num_threads: usize,
-    ) {
-        let limits_copy = search_limits.clone();
-        let board_copy = board.clone();
-
-        let mut handles = Vec::new();
-        for i in 0..num_threads {
-            let tt_copy = Arc::clone(tt);
-
-            let handle = thread::Builder::new()
-                .stack_size(32 * 1024 * 1024)
-                .name(format!("Engine Thread {i}"))
-                .spawn(move || {
-                    let thread_board = board_copy.clone();
-                    let thread_limits = limits_copy.clone();
-                    let mut searcher = Searcher::new(&tt_copy, &thread_board, &thread_limits);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::move_ply;
+    use crate::engine::reporter::SilentReporter;
+    use crate::engine::skill::Skill;
+    use crate::engine::thread_pool::THREAD_STACK_SIZE;
+    use crate::engine::time_manager::TimeManager;
+    use std::thread;
+
+    // a searcher is far too big for the default test thread, so it gets the room the pool gives it
+    fn with_searcher_stack(test: impl FnOnce() + Send + 'static) {
+        thread::Builder::new()
+            .stack_size(THREAD_STACK_SIZE)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
 
-                    searcher.iterative_deepening();
-                });
+    // a searcher whose capture history holds a single bonus, for e4xd5 at depth 10
+    fn searcher_with_history(search_limits: &SearchLimits) -> (Searcher, Board, MovePly) {
+        let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+        let capture = move_ply::find_legal_move("e4d5", &board).unwrap();
 
-            handles.push(handle.unwrap());
-        }
+        let tt = Arc::new(Transposition::new(1, 1));
+        let reporter: Arc<dyn SearchReporter> = Arc::new(SilentReporter);
+        let shared = Arc::new(SharedSearchState::new(1));
+        let mut searcher = Searcher::new(&tt, &board, search_limits, &reporter, 0, &shared);
+        searcher
+            .capture_heuristics
+            .update(&board, &capture, &Vec::new(), 10);
 
-        for handle in handles {
-            handle.join().unwrap();
-        }
+        (searcher, board, capture)
+    }
 
-        let best_move: MovePly = tt.best_move.load(Ordering::Relaxed).into();
+    fn capture_history(searcher: &Searcher, board: &Board, capture: &MovePly) -> i16 {
+        searcher.capture_heuristics.get(
+            capture.to(),
+            board.piece_at(capture.from()),
+            board.piece_at(capture.to()),
+        )
+    }
 
-        println!("bestmove {}\n", best_move);
+    #[test]
+    fn test_prepare_halves_the_history_with_decay() {
+        with_searcher_stack(|| {
+            let search_limits = SearchLimits::new(TimeManager::infinite(), 1, Skill::default());
+            let (mut searcher, board, capture) = searcher_with_history(&search_limits);
+            assert_eq!(capture_history(&searcher, &board, &capture), 100);
+
+            let tt = Arc::clone(&searcher.tt);
+            let reporter = Arc::clone(&searcher.reporter);
+            searcher.prepare(&tt, &board, &search_limits, &reporter);
+            assert_eq!(capture_history(&searcher, &board, &capture), 50);
+        });
+    }
 
-        tt.curr_depth.store(0, Ordering::Relaxed);
-        tt.age();
+    #[test]
+    fn test_prepare_keeps_the_history_without_decay() {
+        with_searcher_stack(|| {
+            let search_limits = SearchLimits::new(TimeManager::infinite(), 1, Skill::default())
+                .with_history_decay(false);
+            let (mut searcher, board, capture) = searcher_with_history(&search_limits);
+
+            let tt = Arc::clone(&searcher.tt);
+            let reporter = Arc::clone(&searcher.reporter);
+            searcher.prepare(&tt, &board, &search_limits, &reporter);
+            searcher.prepare(&tt, &board, &search_limits, &reporter);
+            assert_eq!(capture_history(&searcher, &board, &capture), 100);
+        });
     }
 }
@@ -1,27 +1,81 @@
+use crate::engine::skill::Skill;
+use crate::engine::time_manager::{IterationReport, TimeManager};
 use std::time::Instant;
 
+pub const MAX_SEARCH_DEPTH: u8 = 63;
+
 #[derive(Copy, Clone)]
 pub struct SearchLimits {
     timer: Instant,
-    soft_stop: u32,
-    hard_stop: u32,
+    time_manager: TimeManager,
+    multi_pv: usize,
+    skill: Skill,
+    max_depth: u8,
+    node_limit: Option<u64>,
+    // whether the history left over from the last search is shrunk before this one starts
+    history_decay: bool,
 }
 
 impl SearchLimits {
-    pub fn new(soft_stop: u32, hard_stop: u32) -> SearchLimits {
+    pub fn new(time_manager: TimeManager, multi_pv: usize, skill: Skill) -> SearchLimits {
         SearchLimits {
             timer: Instant::now(),
-            soft_stop,
-            hard_stop,
+            time_manager,
+            multi_pv,
+            skill,
+            max_depth: MAX_SEARCH_DEPTH,
+            node_limit: None,
+            history_decay: true,
         }
     }
 
-    pub fn is_soft_stop(&self) -> bool {
-        self.soft_stop <= self.timer.elapsed().as_millis() as u32
+    pub fn with_node_limit(mut self, node_limit: u64) -> SearchLimits {
+        self.node_limit = Some(node_limit);
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: u8) -> SearchLimits {
+        self.max_depth = max_depth.clamp(1, MAX_SEARCH_DEPTH);
+        self
+    }
+
+    pub fn with_history_decay(mut self, history_decay: bool) -> SearchLimits {
+        self.history_decay = history_decay;
+        self
+    }
+
+    pub fn history_decay(&self) -> bool {
+        self.history_decay
+    }
+
+    pub fn max_depth(&self) -> u8 {
+        self.max_depth
+    }
+
+    // only checked between iterations, the last iteration decides how much longer is worth it
+    pub fn is_soft_stop(&self, report: &IterationReport) -> bool {
+        self.time_manager.soft_limit(report) <= self.timer.elapsed().as_millis() as u32
     }
 
     pub fn is_hard_stop(&self) -> bool {
-        self.hard_stop <= self.timer.elapsed().as_millis() as u32
+        self.time_manager.maximum() <= self.timer.elapsed().as_millis() as u32
+    }
+
+    // a weakened search is held to whichever of the two limits is lower
+    pub fn is_node_limit_reached(&self, nodes: u128) -> bool {
+        [self.node_limit, self.skill.node_limit()]
+            .into_iter()
+            .flatten()
+            .any(|node_limit| nodes >= node_limit as u128)
+    }
+
+    // weakened play needs a few lines to choose from even if the gui only asked for one
+    pub fn multi_pv(&self) -> usize {
+        self.skill.multi_pv(self.multi_pv)
+    }
+
+    pub fn skill(&self) -> &Skill {
+        &self.skill
     }
 
     pub fn ms_elapsed(&self) -> u128 {
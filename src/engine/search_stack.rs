@@ -0,0 +1,43 @@
+use crate::chess::move_ply::MovePly;
+use crate::chess::types::piece::Piece;
+use crate::chess::types::piece::Piece::NoPiece;
+
+pub const NUM_KILLERS: usize = 2;
+
+// everything the search wants to remember about a single ply
+// the moved piece and the move played together form the context that continuation history is indexed by
+#[derive(Copy, Clone)]
+pub struct SearchStackEntry {
+    pub cur_move: MovePly,
+    pub moved_piece: Piece,
+    pub static_eval: i16,
+    pub killers: [MovePly; NUM_KILLERS],
+    pub in_check: bool,
+}
+
+impl Default for SearchStackEntry {
+    fn default() -> Self {
+        SearchStackEntry {
+            cur_move: MovePly::default(),
+            moved_piece: NoPiece,
+            static_eval: 0,
+            killers: [MovePly::default(); NUM_KILLERS],
+            in_check: false,
+        }
+    }
+}
+
+impl SearchStackEntry {
+    pub fn update_killers(&mut self, killer: MovePly) {
+        if self.killers[0] == killer {
+            return;
+        }
+
+        self.killers[1] = self.killers[0];
+        self.killers[0] = killer;
+    }
+
+    pub fn is_killer(&self, played: MovePly) -> bool {
+        self.killers.contains(&played)
+    }
+}
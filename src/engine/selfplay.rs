@@ -0,0 +1,131 @@
+use crate::chess::board::Board;
+use crate::chess::move_generator::{self, GEN_ALL, MoveGenerator};
+use crate::chess::move_list::MoveList;
+use crate::chess::types::color::Color;
+use crate::engine::arbiter::Arbiter;
+use crate::engine::reporter::{SearchReporter, SilentReporter};
+use crate::engine::search_limits::SearchLimits;
+use crate::engine::thread_pool::{ThreadPool, ThreadResult};
+use crate::engine::transposition::Transposition;
+use crate::engine::types::match_result::MatchResult;
+use rand::Rng;
+use rand::rngs::StdRng;
+use std::sync::Arc;
+
+const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const PLAYER_HASH_MB: u16 = 16;
+
+// games still going after this many plies are only shuffling and get called a draw
+const MAX_GAME_PLIES: usize = 500;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWin,
+    Draw,
+    BlackWin,
+}
+
+impl GameResult {
+    pub fn white_score(self) -> f64 {
+        match self {
+            GameResult::WhiteWin => 1.0,
+            GameResult::Draw => 0.5,
+            GameResult::BlackWin => 0.0,
+        }
+    }
+}
+
+// a single threaded engine with its own table and heuristics, so two players never share what they learn
+pub struct Player {
+    tt: Arc<Transposition>,
+    thread_pool: ThreadPool,
+    search_limits: SearchLimits,
+}
+
+impl Player {
+    // the limits need a node limit or a skill, an infinite search never returns
+    pub fn new(search_limits: SearchLimits) -> Self {
+        Self {
+            tt: Arc::new(Transposition::new(PLAYER_HASH_MB, 1)),
+            thread_pool: ThreadPool::new(1),
+            search_limits,
+        }
+    }
+
+    fn new_game(&self) {
+        self.tt.clear(1);
+        self.thread_pool.new_game();
+    }
+
+    fn think(&self, board: &Board) -> ThreadResult {
+        let reporter: Arc<dyn SearchReporter> = Arc::new(SilentReporter);
+
+        self.thread_pool
+            .start(&self.tt, board, &self.search_limits, &reporter);
+        self.thread_pool.wait(&self.search_limits)
+    }
+}
+
+// a game where the players are done with the book, plies counts from the start position
+pub struct Opening {
+    pub board: Board,
+    pub plies: usize,
+}
+
+// uniformly random moves from the start position, none if the random moves already end the game
+pub fn random_opening(rng: &mut StdRng, min_plies: usize, max_plies: usize) -> Option<Opening> {
+    let mut board = Board::from_fen(START_POS);
+    let plies = rng.gen_range(min_plies, max_plies + 1);
+
+    for _ in 0..plies {
+        let legal = move_generator::legal_moves(&board);
+        if legal.is_empty() {
+            return None;
+        }
+
+        board.play(&legal[rng.gen_range(0, legal.len())]);
+    }
+
+    Some(Opening { board, plies })
+}
+
+// plays the opening out to the end, on_search sees every position together with the search that was played in it
+pub fn play_game(
+    opening: &Opening,
+    white: &Player,
+    black: &Player,
+    mut on_search: impl FnMut(&Board, usize, &ThreadResult),
+) -> GameResult {
+    white.new_game();
+    black.new_game();
+
+    let mut board = opening.board.clone();
+
+    for plies in opening.plies..opening.plies + MAX_GAME_PLIES {
+        let mut move_list = MoveList::default();
+        MoveGenerator::<GEN_ALL>::fill(&board, &mut move_list);
+
+        match Arbiter::arbitrate(&board, &move_list) {
+            MatchResult::Loss => {
+                return match board.side_to_move() {
+                    Color::White => GameResult::BlackWin,
+                    Color::Black => GameResult::WhiteWin,
+                };
+            }
+            MatchResult::Draw => return GameResult::Draw,
+            MatchResult::NoResult => {}
+        }
+
+        let player = match board.side_to_move() {
+            Color::White => white,
+            Color::Black => black,
+        };
+
+        let result = player.think(&board);
+        on_search(&board, plies, &result);
+        board.play(&result.best_move);
+    }
+
+    GameResult::Draw
+}
@@ -0,0 +1,197 @@
+use crate::engine::root_move::RootMove;
+use rand::Rng;
+
+pub const MAX_SKILL_LEVEL: u8 = 20;
+
+// weakened play always searches a few lines so there is a plausible alternative to the best move
+const MIN_SKILL_MULTI_PV: usize = 4;
+
+// moves more than this many centipawns behind the best one don't get any more of a random push
+const MAX_SKILL_SCORE_SPREAD: i32 = 100;
+
+// node limit per move and rating for every skill level below full strength
+// each level played 300 games against the one below it with `gauntlet 1 300 <file>` and the gaps add up to the
+// ratings, the errors are 95% intervals and add up to about +/- 160 between level 0 and level 19
+// there were no rated opponents to play, so level 0 being 1000 is where the scale is put rather than a measurement
+// rerun the gauntlet whenever the search or the network changes
+const SKILL_TABLE: [(u64, u16); MAX_SKILL_LEVEL as usize] = [
+    (128, 1000),
+    (160, 1074),   // +74 +/- 36 over level 0
+    (200, 1116),   // +42 +/- 36 over level 1
+    (256, 1156),   // +40 +/- 36 over level 2
+    (320, 1175),   // +20 +/- 36 over level 3
+    (400, 1181),   // +6 +/- 35 over level 4
+    (512, 1226),   // +45 +/- 35 over level 5
+    (768, 1284),   // +57 +/- 36 over level 6
+    (1024, 1309),  // +26 +/- 36 over level 7
+    (1536, 1345),  // +36 +/- 37 over level 8
+    (2048, 1401),  // +56 +/- 38 over level 9
+    (3072, 1448),  // +47 +/- 38 over level 10
+    (4096, 1524),  // +76 +/- 38 over level 11
+    (6144, 1636),  // +111 +/- 39 over level 12
+    (8192, 1698),  // +62 +/- 37 over level 13
+    (12288, 1837), // +139 +/- 39 over level 14
+    (16384, 1918), // +81 +/- 38 over level 15
+    (24576, 1997), // +79 +/- 38 over level 16
+    (32768, 2082), // +85 +/- 38 over level 17
+    (49152, 2162), // +80 +/- 37 over level 18
+];
+
+pub const MIN_ELO: u16 = SKILL_TABLE[0].1;
+pub const MAX_ELO: u16 = SKILL_TABLE[MAX_SKILL_LEVEL as usize - 1].1;
+
+// the level is fractional so an elo between two table entries plays between them
+#[derive(Copy, Clone)]
+pub struct Skill {
+    level: f64,
+    node_limit: Option<u64>,
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self {
+            level: MAX_SKILL_LEVEL as f64,
+            node_limit: None,
+        }
+    }
+}
+
+impl Skill {
+    // UCI_Elo takes over from the skill level as soon as UCI_LimitStrength is on
+    pub fn new(limit_strength: bool, elo: u16, skill_level: u8) -> Self {
+        if limit_strength {
+            Self::from_elo(elo)
+        } else {
+            Self::from_level(skill_level)
+        }
+    }
+
+    pub fn from_level(level: u8) -> Self {
+        if level >= MAX_SKILL_LEVEL {
+            return Self::default();
+        }
+
+        Self {
+            level: level as f64,
+            node_limit: Some(SKILL_TABLE[level as usize].0),
+        }
+    }
+
+    pub fn from_elo(elo: u16) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+
+        let Some(index) = SKILL_TABLE.windows(2).position(|pair| elo < pair[1].1) else {
+            return Self::from_level(MAX_SKILL_LEVEL - 1);
+        };
+
+        let (lower_nodes, lower_elo) = SKILL_TABLE[index];
+        let (upper_nodes, upper_elo) = SKILL_TABLE[index + 1];
+        let fraction = (elo - lower_elo) as f64 / (upper_elo - lower_elo) as f64;
+
+        // the node limits grow geometrically so they are interpolated that way too
+        let node_limit =
+            lower_nodes as f64 * (upper_nodes as f64 / lower_nodes as f64).powf(fraction);
+
+        Self {
+            level: index as f64 + fraction,
+            node_limit: Some(node_limit as u64),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.level < MAX_SKILL_LEVEL as f64
+    }
+
+    pub fn node_limit(&self) -> Option<u64> {
+        self.node_limit
+    }
+
+    pub fn multi_pv(&self, multi_pv: usize) -> usize {
+        if self.is_enabled() {
+            multi_pv.max(MIN_SKILL_MULTI_PV)
+        } else {
+            multi_pv
+        }
+    }
+
+    // every line gets a push towards the best score plus a random one, both bigger the lower the level
+    // a move that is slightly worse gets picked now and then, one that loses material almost never
+    pub fn pick_move<'a>(&self, lines: &'a [RootMove]) -> &'a RootMove {
+        let top_score = lines[0].score as i32;
+        let bottom_score = lines[lines.len() - 1].score as i32;
+        let spread = (top_score - bottom_score).min(MAX_SKILL_SCORE_SPREAD) as f64;
+        let weakness = 120.0 - 2.0 * self.level;
+
+        let mut rng = rand::thread_rng();
+        let mut best_line = &lines[0];
+        let mut best_value = f64::MIN;
+
+        for line in lines {
+            let score_gap = (top_score - line.score as i32) as f64;
+            let push = (weakness * score_gap + spread * rng.gen_range(0.0, weakness)) / 128.0;
+            let value = line.score as f64 + push;
+
+            if value > best_value {
+                best_value = value;
+                best_line = line;
+            }
+        }
+
+        best_line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skill_table_is_increasing() {
+        // a rerun of the gauntlet where a level comes out weaker than the one below would break from_elo
+        assert!(
+            SKILL_TABLE
+                .windows(2)
+                .all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1)
+        );
+    }
+
+    #[test]
+    fn test_from_elo_matches_the_table() {
+        for (level, (nodes, elo)) in SKILL_TABLE.iter().enumerate() {
+            let skill = Skill::from_elo(*elo);
+            assert_eq!(skill.node_limit(), Some(*nodes));
+            assert_eq!(skill.level, level as f64);
+        }
+
+        assert_eq!(Skill::from_elo(0).node_limit(), Some(SKILL_TABLE[0].0));
+        assert_eq!(
+            Skill::from_elo(u16::MAX).node_limit(),
+            Some(SKILL_TABLE[MAX_SKILL_LEVEL as usize - 1].0)
+        );
+    }
+
+    #[test]
+    fn test_from_elo_interpolates_between_levels() {
+        let (lower_nodes, lower_elo) = SKILL_TABLE[3];
+        let (upper_nodes, upper_elo) = SKILL_TABLE[4];
+        let skill = Skill::from_elo((lower_elo + upper_elo) / 2);
+
+        let node_limit = skill.node_limit().unwrap();
+        assert!(lower_nodes < node_limit && node_limit < upper_nodes);
+        assert!(3.0 < skill.level && skill.level < 4.0);
+    }
+
+    #[test]
+    fn test_limit_strength_picks_elo_over_level() {
+        assert!(!Skill::new(false, MIN_ELO, MAX_SKILL_LEVEL).is_enabled());
+        assert!(Skill::new(true, MAX_ELO, MAX_SKILL_LEVEL).is_enabled());
+        assert_eq!(
+            Skill::new(true, MIN_ELO, MAX_SKILL_LEVEL).node_limit(),
+            Some(SKILL_TABLE[0].0)
+        );
+        assert_eq!(
+            Skill::new(false, MIN_ELO, 5).node_limit(),
+            Some(SKILL_TABLE[5].0)
+        );
+    }
+}
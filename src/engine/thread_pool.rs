@@ -0,0 +1,300 @@
+use crate::chess::board::Board;
+use crate::chess::move_ply::MovePly;
+use crate::engine::reporter::SearchReporter;
+use crate::engine::score::MATE_IN_MAX_PLY;
+use crate::engine::search::Searcher;
+use crate::engine::search_limits::SearchLimits;
+use crate::engine::transposition::Transposition;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+pub const MAIN_THREAD: usize = 0;
+
+pub const THREAD_STACK_SIZE: usize = 32 * 1024 * 1024;
+
+// keeps each counter on its own cache line so threads don't fight over them
+#[derive(Default)]
+#[repr(align(64))]
+struct NodeCounter(AtomicU64);
+
+// everything the threads of a single search need to see from each other besides the tt
+pub struct SharedSearchState {
+    stop: AtomicBool,
+    nodes: Box<[NodeCounter]>,
+}
+
+impl SharedSearchState {
+    pub fn new(num_threads: usize) -> Self {
+        Self {
+            stop: AtomicBool::new(false),
+            nodes: (0..num_threads).map(|_| NodeCounter::default()).collect(),
+        }
+    }
+
+    fn reset(&self) {
+        self.stop.store(false, Ordering::Relaxed);
+        for counter in self.nodes.iter() {
+            counter.0.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub fn set_nodes(&self, thread_id: usize, nodes: u64) {
+        self.nodes[thread_id].0.store(nodes, Ordering::Relaxed);
+    }
+
+    pub fn total_nodes(&self) -> u64 {
+        self.nodes
+            .iter()
+            .map(|counter| counter.0.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+struct SearchJob {
+    tt: Arc<Transposition>,
+    board: Board,
+    search_limits: SearchLimits,
+    reporter: Arc<dyn SearchReporter>,
+}
+
+enum WorkerMessage {
+    Search(Box<SearchJob>),
+    NewGame,
+}
+
+#[derive(Copy, Clone)]
+pub struct ThreadResult {
+    pub thread_id: usize,
+    pub best_move: MovePly,
+    pub ponder_move: Option<MovePly>,
+    pub score: i16,
+    pub depth: u8,
+}
+
+struct Worker {
+    job_sender: Sender<WorkerMessage>,
+    handle: JoinHandle<()>,
+}
+
+// lazy smp, every thread searches the same position and they only help each other through the tt
+// the main thread is the only one that reports and decides when the search is over,
+// the helpers keep going until it tells them to stop
+pub struct ThreadPool {
+    workers: Vec<Worker>,
+    result_receiver: Receiver<ThreadResult>,
+    shared: Arc<SharedSearchState>,
+}
+
+impl ThreadPool {
+    pub fn new(num_threads: usize) -> Self {
+        let num_threads = num_threads.max(1);
+        let shared = Arc::new(SharedSearchState::new(num_threads));
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let workers = (0..num_threads)
+            .map(|thread_id| Self::spawn_worker(thread_id, &shared, result_sender.clone()))
+            .collect();
+
+        Self {
+            workers,
+            result_receiver,
+            shared,
+        }
+    }
+
+    fn spawn_worker(
+        thread_id: usize,
+        shared: &Arc<SharedSearchState>,
+        result_sender: Sender<ThreadResult>,
+    ) -> Worker {
+        let (job_sender, job_receiver) = mpsc::channel::<WorkerMessage>();
+        let shared = Arc::clone(shared);
+
+        let handle = thread::Builder::new()
+            .stack_size(THREAD_STACK_SIZE)
+            .name(format!("Engine Thread {thread_id}"))
+            .spawn(move || {
+                // each worker holds on to its searcher so the heuristics survive between moves
+                let mut searcher: Option<Searcher> = None;
+
+                // the loop ends once the pool drops its sender
+                for message in job_receiver {
+                    let job = match message {
+                        WorkerMessage::Search(job) => job,
+                        WorkerMessage::NewGame => {
+                            if let Some(searcher) = &mut searcher {
+                                searcher.clear_heuristics();
+                            }
+                            continue;
+                        }
+                    };
+
+                    let searcher = match &mut searcher {
+                        Some(searcher) => {
+                            searcher.prepare(
+                                &job.tt,
+                                &job.board,
+                                &job.search_limits,
+                                &job.reporter,
+                            );
+                            searcher
+                        }
+                        None => searcher.insert(Searcher::new(
+                            &job.tt,
+                            &job.board,
+                            &job.search_limits,
+                            &job.reporter,
+                            thread_id,
+                            &shared,
+                        )),
+                    };
+
+                    let result = searcher.iterative_deepening();
+
+                    if thread_id == MAIN_THREAD {
+                        shared.stop();
+                    }
+
+                    result_sender.send(result).unwrap();
+                }
+            })
+            .unwrap();
+
+        Worker { job_sender, handle }
+    }
+
+    pub fn search_start(
+        &self,
+        tt: &Arc<Transposition>,
+        board: &Board,
+        search_limits: &SearchLimits,
+        reporter: &Arc<dyn SearchReporter>,
+    ) -> MovePly {
+        self.start(tt, board, search_limits, reporter);
+        let best_result = self.wait(search_limits);
+        reporter.best_move(board, &best_result);
+
+        best_result.best_move
+    }
+
+    // hands the position to every thread and returns straight away, wait collects the result
+    pub fn start(
+        &self,
+        tt: &Arc<Transposition>,
+        board: &Board,
+        search_limits: &SearchLimits,
+        reporter: &Arc<dyn SearchReporter>,
+    ) {
+        // entries from earlier searches become the first to be replaced
+        tt.age();
+        self.shared.reset();
+
+        for worker in &self.workers {
+            let job = SearchJob {
+                tt: Arc::clone(tt),
+                board: board.clone(),
+                search_limits: *search_limits,
+                reporter: Arc::clone(reporter),
+            };
+
+            worker
+                .job_sender
+                .send(WorkerMessage::Search(Box::new(job)))
+                .unwrap();
+        }
+    }
+
+    // tells every thread to finish up, the result still has to be collected with wait
+    pub fn stop(&self) {
+        self.shared.stop();
+    }
+
+    // the main thread raises the flag itself when it is done, so this is also true once a search ends on its own
+    pub fn is_stopped(&self) -> bool {
+        self.shared.is_stopped()
+    }
+
+    pub fn wait(&self, search_limits: &SearchLimits) -> ThreadResult {
+        let mut results: Vec<ThreadResult> = (0..self.workers.len())
+            .map(|_| self.result_receiver.recv().unwrap())
+            .collect();
+        results.sort_by_key(|result| result.thread_id);
+
+        // a weakened main thread picked its move on purpose, voting would only undo that
+        let best_thread = if search_limits.skill().is_enabled() {
+            MAIN_THREAD
+        } else {
+            Self::best_thread(&results)
+        };
+
+        results[best_thread]
+    }
+
+    // messages are handled in order, so the next search is guaranteed to start from clean heuristics
+    pub fn new_game(&self) {
+        for worker in &self.workers {
+            worker.job_sender.send(WorkerMessage::NewGame).unwrap();
+        }
+    }
+
+    // every thread votes for its best move, weighted by how deep it got and how good it thinks the move is
+    // a thread that found a mate gets the final say since nothing can beat that
+    fn best_thread(results: &[ThreadResult]) -> usize {
+        let min_score = results.iter().map(|result| result.score).min().unwrap();
+
+        let mut votes: HashMap<u16, i64> = HashMap::new();
+        for result in results {
+            let vote = (result.score as i64 - min_score as i64 + 14) * result.depth as i64;
+            *votes.entry(result.best_move.packed_data()).or_default() += vote;
+        }
+
+        let mut best_thread = MAIN_THREAD;
+        for (thread_index, result) in results.iter().enumerate() {
+            let best = &results[best_thread];
+
+            // threads that never finished an iteration don't have a move worth playing
+            if result.depth == 0 || result.best_move.is_default() {
+                continue;
+            }
+
+            if best.score >= MATE_IN_MAX_PLY {
+                if result.score > best.score {
+                    best_thread = thread_index;
+                }
+                continue;
+            }
+
+            let result_votes = votes[&result.best_move.packed_data()];
+            let best_votes = votes[&best.best_move.packed_data()];
+
+            if result.score >= MATE_IN_MAX_PLY
+                || result_votes > best_votes
+                || (result_votes == best_votes && result.score > best.score)
+            {
+                best_thread = thread_index;
+            }
+        }
+
+        best_thread
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            drop(worker.job_sender);
+            worker.handle.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::chess::types::color::Color;
+
+// used when the gui doesn't say how many moves are left until the next time control
+const DEFAULT_MOVES_TO_GO: u32 = 50;
+const MAX_MOVES_TO_GO: u32 = 50;
+
+// used when go has no time information at all
+const DEFAULT_THINK_TIME: u32 = 1000;
+
+// the optimum is a fraction of the base time, the search can stretch up to the maximum
+// when it isn't sure about its move but never further than a share of what is left
+const OPTIMUM_PERCENT: u32 = 60;
+const MAXIMUM_MULTIPLIER: u32 = 3;
+const MAX_CLOCK_PERCENT: u32 = 75;
+
+// with little time left only a small slice of it is ever used per move
+const EMERGENCY_TIME: u32 = 1000;
+const EMERGENCY_CLOCK_PERCENT: u32 = 25;
+
+// scales the optimum by how many iterations in a row the best move stayed the same
+const STABILITY_SCALES: [f64; 5] = [2.2, 1.4, 1.0, 0.85, 0.75];
+
+// a falling score makes the search think longer, up to this factor
+const MAX_SCORE_DROP_SCALE: f64 = 1.6;
+const SCORE_DROP_DIVISOR: f64 = 150.0;
+
+// the more of its nodes went into the best move, the more certain the search is about it
+const NODE_FRACTION_BASE: f64 = 1.6;
+const NODE_FRACTION_MULTIPLIER: f64 = 1.4;
+
+// the time related parts of a go command, already picked for the side to move
+#[derive(Copy, Clone, Default)]
+pub struct TimeControl {
+    pub time_left: Option<u32>,
+    pub increment: Option<u32>,
+    pub moves_to_go: Option<u32>,
+    pub move_time: Option<u32>,
+    pub move_overhead: u32,
+}
+
+impl TimeControl {
+    pub fn from_go(
+        side_to_move: Color,
+        move_time: Option<u32>,
+        wtime: Option<u32>,
+        btime: Option<u32>,
+        winc: Option<u32>,
+        binc: Option<u32>,
+        moves_to_go: Option<u32>,
+    ) -> Self {
+        let (time_left, increment) = match side_to_move {
+            Color::White => (wtime, winc),
+            Color::Black => (btime, binc),
+        };
+
+        Self {
+            time_left,
+            increment,
+            moves_to_go,
+            move_time,
+            ..Self::default()
+        }
+    }
+}
+
+// what the main thread learned from the last completed iteration
+#[derive(Copy, Clone)]
+pub struct IterationReport {
+    pub best_move_stability: usize,
+    pub score_drop: i16,
+    pub best_move_node_fraction: f64,
+}
+
+#[derive(Copy, Clone)]
+pub struct TimeManager {
+    optimum: u32,
+    maximum: u32,
+}
+
+impl TimeManager {
+    pub fn new(time_control: &TimeControl) -> Self {
+        let move_overhead = time_control.move_overhead;
+
+        if let Some(move_time) = time_control.move_time {
+            let think_time = move_time.saturating_sub(move_overhead).max(1);
+
+            return Self {
+                optimum: think_time,
+                maximum: think_time,
+            };
+        }
+
+        let Some(time_left) = time_control.time_left else {
+            return Self {
+                optimum: DEFAULT_THINK_TIME,
+                maximum: DEFAULT_THINK_TIME,
+            };
+        };
+
+        let increment = time_control.increment.unwrap_or(0) as u64;
+        let moves_to_go = time_control
+            .moves_to_go
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .clamp(1, MAX_MOVES_TO_GO) as u64;
+
+        // the overhead is lost on every move until the next time control, so it is put aside up front
+        let reserved_overhead = move_overhead as u64 * (moves_to_go + 2);
+        let usable_time = (time_left as u64 + increment * (moves_to_go - 1))
+            .saturating_sub(reserved_overhead)
+            .max(1);
+
+        let base_time = usable_time / moves_to_go;
+
+        // whatever happens the search has to finish with the overhead still left on the clock
+        let clock_limit = time_left.saturating_sub(move_overhead).max(1) as u64;
+
+        let clock_percent = if time_left < EMERGENCY_TIME {
+            EMERGENCY_CLOCK_PERCENT
+        } else {
+            MAX_CLOCK_PERCENT
+        };
+        let clock_cap = clock_limit * clock_percent as u64 / 100;
+
+        let maximum = (base_time * MAXIMUM_MULTIPLIER as u64)
+            .min(clock_cap)
+            .clamp(1, clock_limit) as u32;
+        let optimum = (base_time * OPTIMUM_PERCENT as u64 / 100).clamp(1, maximum as u64) as u32;
+
+        Self { optimum, maximum }
+    }
+
+    // analysis runs until the gui stops it
+    pub fn infinite() -> Self {
+        Self {
+            optimum: u32::MAX,
+            maximum: u32::MAX,
+        }
+    }
+
+    pub fn maximum(&self) -> u32 {
+        self.maximum
+    }
+
+    pub fn soft_limit(&self, report: &IterationReport) -> u32 {
+        let stability_index = report.best_move_stability.min(STABILITY_SCALES.len() - 1);
+        let stability_scale = STABILITY_SCALES[stability_index];
+
+        let score_drop = report.score_drop.max(0) as f64;
+        let score_drop_scale = (1.0 + score_drop / SCORE_DROP_DIVISOR).min(MAX_SCORE_DROP_SCALE);
+
+        let node_fraction_scale =
+            (NODE_FRACTION_BASE - report.best_move_node_fraction) * NODE_FRACTION_MULTIPLIER;
+
+        let scale = stability_scale * score_drop_scale * node_fraction_scale;
+
+        ((self.optimum as f64 * scale) as u32).min(self.maximum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOVE_OVERHEAD: u32 = 20;
+
+    // the worst a search can do is use its whole hard limit and then lose the overhead on top
+    fn worst_case_move_time(time_control: &TimeControl) -> u32 {
+        TimeManager::new(time_control).maximum() + time_control.move_overhead
+    }
+
+    // plays num_moves moves and returns the move number the clock ran out on, if it did
+    fn simulate_game(
+        initial_time: u32,
+        increment: u32,
+        moves_per_control: Option<u32>,
+        num_moves: u32,
+    ) -> Option<u32> {
+        let mut time_left = initial_time;
+        let mut moves_to_go = moves_per_control;
+
+        for move_number in 0..num_moves {
+            let time_control = TimeControl {
+                time_left: Some(time_left),
+                increment: Some(increment),
+                moves_to_go,
+                move_time: None,
+                move_overhead: MOVE_OVERHEAD,
+            };
+
+            let time_used = worst_case_move_time(&time_control);
+            if time_used >= time_left {
+                return Some(move_number);
+            }
+
+            time_left = time_left - time_used + increment;
+
+            if let (Some(left), Some(per_control)) = (moves_to_go, moves_per_control) {
+                moves_to_go = Some(left - 1);
+
+                if left == 1 {
+                    moves_to_go = Some(per_control);
+                    time_left += initial_time;
+                }
+            }
+        }
+
+        None
+    }
+
+    #[test]
+    fn sudden_death_never_flags() {
+        assert_eq!(simulate_game(60_000, 0, None, 100), None);
+        assert_eq!(simulate_game(10_000, 0, None, 80), None);
+    }
+
+    #[test]
+    fn increment_never_flags() {
+        assert_eq!(simulate_game(2_000, 100, None, 500), None);
+        assert_eq!(simulate_game(100, 50, None, 500), None);
+    }
+
+    #[test]
+    fn moves_to_go_never_flags() {
+        assert_eq!(simulate_game(10_000, 0, Some(40), 200), None);
+        assert_eq!(simulate_game(1_000, 0, Some(40), 200), None);
+        assert_eq!(simulate_game(500, 0, Some(1), 50), None);
+    }
+
+    #[test]
+    fn hard_limit_keeps_the_overhead() {
+        for time_left in [1, 10, 21, 50, 100, 1_000, 100_000] {
+            let time_control = TimeControl {
+                time_left: Some(time_left),
+                increment: Some(10_000),
+                moves_to_go: Some(1),
+                move_time: None,
+                move_overhead: MOVE_OVERHEAD,
+            };
+
+            let maximum = TimeManager::new(&time_control).maximum();
+            assert!(maximum <= time_left.saturating_sub(MOVE_OVERHEAD).max(1));
+        }
+    }
+
+    #[test]
+    fn soft_limit_stays_under_hard_limit() {
+        let time_control = TimeControl {
+            time_left: Some(30_000),
+            increment: Some(300),
+            move_overhead: MOVE_OVERHEAD,
+            ..TimeControl::default()
+        };
+        let time_manager = TimeManager::new(&time_control);
+
+        let unstable = IterationReport {
+            best_move_stability: 0,
+            score_drop: i16::MAX,
+            best_move_node_fraction: 0.0,
+        };
+        let stable = IterationReport {
+            best_move_stability: usize::MAX,
+            score_drop: i16::MIN,
+            best_move_node_fraction: 1.0,
+        };
+
+        assert!(time_manager.soft_limit(&unstable) <= time_manager.maximum());
+        assert!(time_manager.soft_limit(&stable) <= time_manager.soft_limit(&unstable));
+    }
+
+    #[test]
+    fn move_time_subtracts_overhead() {
+        let time_control = TimeControl {
+            move_time: Some(1_000),
+            move_overhead: MOVE_OVERHEAD,
+            ..TimeControl::default()
+        };
+
+        assert_eq!(
+            TimeManager::new(&time_control).maximum(),
+            1_000 - MOVE_OVERHEAD
+        );
+    }
+}
@@ -1,153 +1,447 @@
 use crate::chess::move_ply::MovePly;
+use crate::engine::score;
 use crate::engine::types::tt_flag::TTFlag;
-use portable_atomic::{AtomicU8, AtomicU32, AtomicU128};
-use std::mem;
-use std::sync::atomic::{AtomicI16, AtomicU16, Ordering};
-
-// Type     Bits
-// zobrist: 64
-// curmove: 16
-// eval   : 16
-// depth  : 8
-// score  : 8
-// gen    : 8
-// flag   : 2
+use crate::precomputed::accessor::ZOBRIST;
+use portable_atomic::AtomicU8;
+use std::alloc::{self, Layout};
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{_MM_HINT_T0, _mm_prefetch};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::atomic::{AtomicU16, AtomicU64, Ordering};
+use std::{mem, ptr, thread};
+
+// Type         Bits
+// curmove    : 16
+// eval       : 16
+// static eval: 16
+// depth      : 8
+// gen        : 6
+// flag       : 2
 
 #[derive(Copy, Clone, Default)]
+#[repr(C)]
 pub struct TTEntry {
-    pub zobrist: u64,
     pub cur_move: MovePly,
     pub eval: i16,
+    pub static_eval: i16,
     pub depth: u8,
-    pub score: u8,
-    pub generation: u8,
-    pub tt_flag: TTFlag,
+    generation_flag: u8,
 }
 
-impl From<u128> for TTEntry {
-    fn from(packed_data: u128) -> Self {
+impl From<u64> for TTEntry {
+    fn from(packed_data: u64) -> Self {
         unsafe { mem::transmute(packed_data) }
     }
 }
 
-const AGE_MULTIPLIER: i16 = -1;
+impl From<TTEntry> for u64 {
+    fn from(entry: TTEntry) -> Self {
+        unsafe { mem::transmute(entry) }
+    }
+}
+
+const FLAG_BITS: u8 = 2;
+const FLAG_MASK: u8 = (1 << FLAG_BITS) - 1;
+const GENERATION_MASK: u8 = u8::MAX >> FLAG_BITS;
+
+impl TTEntry {
+    // the generation is filled in by the table when the entry is stored
+    pub fn new(
+        cur_move: MovePly,
+        eval: i16,
+        static_eval: i16,
+        depth: u8,
+        tt_flag: TTFlag,
+        ply: u8,
+    ) -> Self {
+        TTEntry {
+            cur_move,
+            eval: score::to_tt(eval, ply),
+            static_eval,
+            depth,
+            generation_flag: tt_flag as u8,
+        }
+    }
+
+    pub fn tt_flag(&self) -> TTFlag {
+        TTFlag::from(self.generation_flag & FLAG_MASK)
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation_flag >> FLAG_BITS
+    }
+
+    // the entry score is used to decide which entry is worth keeping
+    fn score(&self) -> i16 {
+        let mut score = self.depth as i16 * DEPTH_MULTIPLIER;
+        if let TTFlag::Exact = self.tt_flag() {
+            score += EXACT_BONUS;
+        }
+
+        score
+    }
+}
+
+const AGE_MULTIPLIER: i16 = -8;
 const EXACT_BONUS: i16 = 12;
 const DEPTH_MULTIPLIER: i16 = 4;
 
-const ENTRY_SIZE: usize = std::mem::size_of::<TTEntry>();
+// a shallower search of the same position may still overwrite a slightly deeper one
+const SAME_POSITION_DEPTH_MARGIN: u8 = 2;
 
-pub struct Transposition {
-    entries: Box<[AtomicU128]>,
-    pub generation: AtomicU8,
+const ENTRIES_PER_BUCKET: usize = 3;
+
+// number of entries looked at to estimate how full the table is
+const HASH_FULL_SAMPLE_SIZE: usize = 999;
 
-    pub curr_depth: AtomicU8,
-    entries_filled: AtomicU32,
-    pub best_move_score: AtomicI16,
-    pub best_move: AtomicU16,
+// each entry is split into an atomic 16 bit key and an atomic 64 bit data section
+// the key is stored xored with a fold of the data so that a probe reading the key
+// and data of two different writes will (almost always) fail the verification
+#[derive(Default)]
+#[repr(C, align(32))]
+struct Bucket {
+    keys: [AtomicU16; ENTRIES_PER_BUCKET],
+    data: [AtomicU64; ENTRIES_PER_BUCKET],
 }
 
-impl Transposition {
-    pub fn new(mb_size: u16) -> Self {
-        let size_as_bytes = mb_size as u64 * 1024 * 1024;
-        let max_num_entries = size_as_bytes / ENTRY_SIZE as u64;
+const BUCKET_SIZE: usize = mem::size_of::<Bucket>();
+const _: () = assert!(BUCKET_SIZE == 32);
 
-        let round_down_pow2 = 1 << (63 - max_num_entries.leading_zeros());
+// hash file layout, all little endian
+// magic        : 8 bytes
+// version      : u32
+// bucket size  : u32
+// entries      : u32 (per bucket)
+// generation   : u32
+// zobrist keys : u64 (fingerprint)
+// num buckets  : u64
+// followed by every entry as its stored u16 key and u64 data
+const HASH_FILE_MAGIC: &[u8; 8] = b"RCEHASH\0";
+const HASH_FILE_VERSION: u32 = 1;
+const HASH_FILE_HEADER_SIZE: u64 = 40;
+const HASH_FILE_ENTRY_SIZE: u64 = 10;
 
-        let entries: Vec<AtomicU128> = (0..round_down_pow2).map(|_| AtomicU128::new(0)).collect();
+impl Bucket {
+    fn fold(data: u64) -> u16 {
+        (data ^ (data >> 16) ^ (data >> 32) ^ (data >> 48)) as u16
+    }
+
+    fn load(&self, index: usize) -> (u16, u64) {
+        let data = self.data[index].load(Ordering::Relaxed);
+        let key = self.keys[index].load(Ordering::Relaxed) ^ Self::fold(data);
+
+        (key, data)
+    }
+
+    fn store(&self, index: usize, key: u16, entry: TTEntry) {
+        let data: u64 = entry.into();
+
+        self.data[index].store(data, Ordering::Relaxed);
+        self.keys[index].store(key ^ Self::fold(data), Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        for i in 0..ENTRIES_PER_BUCKET {
+            self.data[i].store(0, Ordering::Relaxed);
+            self.keys[i].store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+pub struct Transposition {
+    buckets: Box<[Bucket]>,
+    generation: AtomicU8,
+}
+
+impl Transposition {
+    pub fn new(mb_size: u16, num_threads: usize) -> Self {
+        let size_as_bytes = mb_size as usize * 1024 * 1024;
+        // a table without buckets would be a zero sized allocation with nothing to index into
+        let num_buckets = (size_as_bytes / BUCKET_SIZE).max(1);
 
         Self {
-            entries: entries.into(),
+            buckets: Self::allocate_zeroed(num_buckets, num_threads),
             generation: AtomicU8::new(0),
-            curr_depth: AtomicU8::new(0),
-            entries_filled: AtomicU32::new(0),
-            best_move: AtomicU16::new(0),
-            best_move_score: AtomicI16::new(0),
         }
     }
 
+    // zeroing gigabytes of memory from a single thread can take seconds
+    // so the buckets are split into chunks that each thread clears on its own
+    fn allocate_zeroed(num_buckets: usize, num_threads: usize) -> Box<[Bucket]> {
+        let layout = Layout::array::<Bucket>(num_buckets).unwrap();
+        let buckets_ptr = unsafe { alloc::alloc(layout) as *mut Bucket };
+        if buckets_ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+
+        let chunk_size = num_buckets.div_ceil(num_threads.max(1));
+        let buckets_addr = buckets_ptr as usize;
+
+        thread::scope(|scope| {
+            for chunk_start in (0..num_buckets).step_by(chunk_size.max(1)) {
+                let chunk_len = chunk_size.min(num_buckets - chunk_start);
+
+                scope.spawn(move || unsafe {
+                    let chunk_ptr = (buckets_addr as *mut Bucket).add(chunk_start);
+                    ptr::write_bytes(chunk_ptr, 0, chunk_len);
+                });
+            }
+        });
+
+        // an all zero bucket is a valid empty bucket
+        unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buckets_ptr, num_buckets)) }
+    }
+
+    // resets every entry without giving the memory back, again split across threads
+    pub fn clear(&self, num_threads: usize) {
+        let chunk_size = self.buckets.len().div_ceil(num_threads.max(1));
+
+        thread::scope(|scope| {
+            for chunk in self.buckets.chunks(chunk_size.max(1)) {
+                scope.spawn(move || chunk.iter().for_each(Bucket::clear));
+            }
+        });
+
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    // only entries from the current search count, since older ones are free to be replaced
     pub fn hash_full(&self) -> u32 {
-        self.entries_filled.load(Ordering::Relaxed) * 1000 / self.entries.len() as u32
+        let generation = self.generation();
+        let num_buckets = (HASH_FULL_SAMPLE_SIZE / ENTRIES_PER_BUCKET).min(self.buckets.len());
+
+        let mut entries_filled = 0;
+        for bucket in &self.buckets[..num_buckets] {
+            for i in 0..ENTRIES_PER_BUCKET {
+                let (_, data) = bucket.load(i);
+
+                if data != 0 && TTEntry::from(data).generation() == generation {
+                    entries_filled += 1;
+                }
+            }
+        }
+
+        (entries_filled * 1000 / (num_buckets * ENTRIES_PER_BUCKET)) as u32
+    }
+
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed) & GENERATION_MASK
     }
 
+    // the generation is free to wrap around, all comparisons between generations wrap as well
     pub fn age(&self) {
-        // dont want an overflow
-        if self.generation.load(Ordering::Relaxed) == u8::MAX {
-            return;
-        }
         self.generation.add(1, Ordering::Relaxed);
     }
 
-    pub fn probe(&self, zobrist: u64) -> Option<TTEntry> {
-        let index = zobrist & (self.entries.len() as u64 - 1);
-        let entry_data = self.entries[index as usize].load(Ordering::Relaxed);
-        let tt_entry: TTEntry = unsafe { mem::transmute(entry_data) };
-        if tt_entry.zobrist == zobrist {
-            return Some(tt_entry);
+    // maps the zobrist onto the table with a multiply and shift
+    // so the number of buckets does not need to be a power of two
+    fn bucket(&self, zobrist: u64) -> &Bucket {
+        let index = (zobrist as u128 * self.buckets.len() as u128) >> 64;
+        &self.buckets[index as usize]
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(HASH_FILE_MAGIC)?;
+        writer.write_all(&HASH_FILE_VERSION.to_le_bytes())?;
+        writer.write_all(&(BUCKET_SIZE as u32).to_le_bytes())?;
+        writer.write_all(&(ENTRIES_PER_BUCKET as u32).to_le_bytes())?;
+        writer.write_all(&(self.generation() as u32).to_le_bytes())?;
+        writer.write_all(&ZOBRIST.fingerprint().to_le_bytes())?;
+        writer.write_all(&(self.buckets.len() as u64).to_le_bytes())?;
+
+        // the raw stored keys are written so the xor verification still holds after loading
+        for bucket in self.buckets.iter() {
+            for i in 0..ENTRIES_PER_BUCKET {
+                let key = bucket.keys[i].load(Ordering::Relaxed);
+                let data = bucket.data[i].load(Ordering::Relaxed);
+
+                writer.write_all(&key.to_le_bytes())?;
+                writer.write_all(&data.to_le_bytes())?;
+            }
         }
-        None
+
+        writer.flush()
     }
 
-    pub fn update(
-        &self,
-        zobrist: u64,
-        cur_move: MovePly,
-        mut eval: i16,
-        depth: u8,
-        tt_flag: TTFlag,
-        _is_pv: bool,
-        ply: u8,
-    ) {
-        if eval > 29000 {
-            eval += ply as i16;
-        } else if eval < -29000 {
-            eval -= ply as i16;
+    // nothing is overwritten until the header and the file length check out,
+    // so a file that doesn't fit leaves the table as it was
+    pub fn load(&self, path: &str) -> io::Result<()> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != HASH_FILE_MAGIC {
+            return Err(invalid_hash_file("file is not a hash file"));
+        }
+
+        if read_u32(&mut reader)? != HASH_FILE_VERSION {
+            return Err(invalid_hash_file("hash file version is not supported"));
+        }
+        if read_u32(&mut reader)? != BUCKET_SIZE as u32
+            || read_u32(&mut reader)? != ENTRIES_PER_BUCKET as u32
+        {
+            return Err(invalid_hash_file("hash file entry layout does not match"));
         }
 
-        // calculate the new entry score
-        let mut score = depth as i16 * DEPTH_MULTIPLIER as i16;
-        match tt_flag {
-            TTFlag::Exact => score += EXACT_BONUS,
-            _ => {}
+        let generation = read_u32(&mut reader)?;
+        if generation > GENERATION_MASK as u32 {
+            return Err(invalid_hash_file("hash file generation is out of range"));
         }
-        let score = score.clamp(0, 255) as u8;
 
-        // new entry
-        let new_entry = TTEntry {
-            zobrist,
-            cur_move,
-            eval,
-            depth,
-            score,
-            generation: self.generation.load(Ordering::Relaxed),
-            tt_flag,
-        };
+        if read_u64(&mut reader)? != ZOBRIST.fingerprint() {
+            return Err(invalid_hash_file(
+                "hash file was made with different zobrist keys",
+            ));
+        }
+        if read_u64(&mut reader)? != self.buckets.len() as u64 {
+            return Err(invalid_hash_file(
+                "hash file was saved with a different Hash size",
+            ));
+        }
 
-        // load old entry
-        let index = (zobrist & (self.entries.len() as u64 - 1)) as usize;
-        let entry: TTEntry = unsafe { mem::transmute(self.entries[index].load(Ordering::Relaxed)) };
+        let num_entries = (self.buckets.len() * ENTRIES_PER_BUCKET) as u64;
+        if file_len != HASH_FILE_HEADER_SIZE + num_entries * HASH_FILE_ENTRY_SIZE {
+            return Err(invalid_hash_file(
+                "hash file is the wrong length for its entries",
+            ));
+        }
 
-        let store_new_entry = || {
-            self.entries[index].store(unsafe { mem::transmute(new_entry) }, Ordering::Relaxed);
-        };
+        for bucket in self.buckets.iter() {
+            for i in 0..ENTRIES_PER_BUCKET {
+                let mut key = [0u8; 2];
+                reader.read_exact(&mut key)?;
+                let data = read_u64(&mut reader)?;
 
-        // if its an empty entry its safe to replace
-        if entry.zobrist == 0 {
-            store_new_entry();
-            self.entries_filled.add(1, Ordering::Relaxed);
-            return;
+                bucket.keys[i].store(u16::from_le_bytes(key), Ordering::Relaxed);
+                bucket.data[i].store(data, Ordering::Relaxed);
+            }
         }
 
-        // if its the same position but the new entry has a higher dpeth
-        if entry.zobrist == new_entry.zobrist && entry.depth < new_entry.depth {
-            store_new_entry();
-            return;
+        self.generation.store(generation as u8, Ordering::Relaxed);
+        Ok(())
+    }
+
+    // fetches the bucket into the cache ahead of the probe
+    #[cfg(target_arch = "x86_64")]
+    pub fn prefetch(&self, zobrist: u64) {
+        let bucket_ptr = self.bucket(zobrist) as *const Bucket as *const i8;
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(bucket_ptr) }
+    }
+
+    // other targets just take the cache miss on the probe
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn prefetch(&self, _zobrist: u64) {}
+
+    // the index only uses the upper bits of the zobrist so the lower bits are left to verify the entry
+    fn key(zobrist: u64) -> u16 {
+        zobrist as u16
+    }
+
+    pub fn probe(&self, zobrist: u64) -> Option<TTEntry> {
+        let bucket = self.bucket(zobrist);
+        let key = Self::key(zobrist);
+
+        for i in 0..ENTRIES_PER_BUCKET {
+            let (entry_key, data) = bucket.load(i);
+
+            if data != 0 && entry_key == key {
+                return Some(TTEntry::from(data));
+            }
         }
 
-        let existing_entry_score = entry.score as i16
-            + (self.generation.load(Ordering::Relaxed) - entry.generation) as i16 * AGE_MULTIPLIER;
+        None
+    }
+
+    pub fn update(&self, zobrist: u64, mut new_entry: TTEntry) {
+        let generation = self.generation();
+        let bucket = self.bucket(zobrist);
+        let key = Self::key(zobrist);
+
+        let mut replace_index = 0;
+        let mut lowest_score = i16::MAX;
+
+        for i in 0..ENTRIES_PER_BUCKET {
+            let (entry_key, data) = bucket.load(i);
+
+            // if its an empty entry its safe to replace
+            if data == 0 {
+                replace_index = i;
+                break;
+            }
+
+            let entry = TTEntry::from(data);
+
+            // the same position is only kept if it was searched a good deal deeper
+            if entry_key == key {
+                let keep_old_entry = entry.generation() == generation
+                    && entry.depth > new_entry.depth + SAME_POSITION_DEPTH_MARGIN
+                    && !matches!(new_entry.tt_flag(), TTFlag::Exact);
+
+                if keep_old_entry {
+                    return;
+                }
+
+                if new_entry.cur_move.is_default() {
+                    new_entry.cur_move = entry.cur_move;
+                }
+
+                replace_index = i;
+                break;
+            }
+
+            let entry_age = generation.wrapping_sub(entry.generation()) & GENERATION_MASK;
+            let entry_score = entry.score() + entry_age as i16 * AGE_MULTIPLIER;
 
-        if existing_entry_score <= score as i16 {
-            store_new_entry();
+            if entry_score < lowest_score {
+                lowest_score = entry_score;
+                replace_index = i;
+            }
         }
+
+        new_entry.generation_flag |= generation << FLAG_BITS;
+        bucket.store(replace_index, key, new_entry);
+    }
+}
+
+fn invalid_hash_file(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_sized_table_still_stores_entries() {
+        let tt = Transposition::new(0, 1);
+        let zobrist = 0x9E37_79B9_7F4A_7C15;
+
+        assert!(tt.probe(zobrist).is_none());
+        tt.update(
+            zobrist,
+            TTEntry::new(MovePly::default(), 35, 20, 6, TTFlag::Exact, 0),
+        );
+
+        let entry = tt.probe(zobrist).unwrap();
+        assert_eq!((entry.eval, entry.depth), (35, 6));
+        assert!(matches!(entry.tt_flag(), TTFlag::Exact));
+        assert!(tt.hash_full() > 0);
     }
 }
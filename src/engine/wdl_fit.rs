@@ -0,0 +1,536 @@
+use crate::chess::board::Board;
+use crate::chess::move_generator;
+use crate::chess::move_ply::MovePly;
+use crate::chess::san;
+use crate::chess::types::color::Color;
+use crate::engine::wdl::{self, AS, BS, MAX_WDL_EVAL};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+
+const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// buckets with fewer positions than this are too noisy to fit on their own
+const MIN_BUCKET_POSITIONS: u64 = 1000;
+
+// the first moves are mostly the random opening and late endgames are mostly shuffling,
+// neither says much about how an eval turns into a result
+const MIN_FULL_MOVE: u32 = 8;
+const MAX_FULL_MOVE: u32 = 120;
+
+// the pattern search stops refining once its step gets this small
+const MIN_STEP: f64 = 0.001;
+
+const WIN: usize = 0;
+const DRAW: usize = 1;
+const LOSS: usize = 2;
+
+// outcome counts per eval, all from the point of view of the side the eval belongs to
+type EvalOutcomes = BTreeMap<i16, [u64; 3]>;
+
+// a single position the fit learns from, the eval and result are both from white's point of view
+struct Sample {
+    material: i32,
+    full_move: u32,
+    eval: i16,
+    result: usize,
+}
+
+// fits the wdl model on either `datagen` output or a pgn of the engine's own games
+// datagen files hold one position per line as "fen | eval | result", with the raw eval
+// and the result 1.0, 0.5 or 0.0, both from white's point of view
+// a pgn needs the evals in the move comments the way cutechess writes them, like {+0.35/12 0.51s}
+// the fitted coefficients are printed in a form that can be pasted straight into wdl.rs
+pub fn run(path: &str) -> io::Result<()> {
+    let (samples, skipped) = if path.ends_with(".pgn") {
+        read_pgn(path)?
+    } else {
+        read_datagen(path)?
+    };
+    let (buckets, left_out) = bucket_samples(&samples);
+
+    let mut fitted_points = Vec::new();
+    for (&material, outcomes) in &buckets {
+        let num_positions: u64 = outcomes.values().flatten().sum();
+        if num_positions < MIN_BUCKET_POSITIONS {
+            continue;
+        }
+
+        let m = wdl::normalized_material(material);
+        let (a, b) = fit_bucket(outcomes, polynomial_guess(m));
+
+        println!("material {material:>2}  positions {num_positions:>9}  a {a:>8.2}  b {b:>7.2}");
+        fitted_points.push((m, a, b, num_positions as f64));
+    }
+
+    println!("skipped {skipped} lines or games that could not be read");
+    println!("left out {left_out} positions outside the fitted move and material range");
+
+    if fitted_points.len() < 4 {
+        println!("not enough material buckets to fit a cubic, try a bigger data set");
+        return Ok(());
+    }
+
+    let a_points: Vec<_> = fitted_points
+        .iter()
+        .map(|&(m, a, _, w)| (m, a, w))
+        .collect();
+    let b_points: Vec<_> = fitted_points
+        .iter()
+        .map(|&(m, _, b, w)| (m, b, w))
+        .collect();
+
+    let fitted_as = fit_cubic(&a_points);
+    let fitted_bs = fit_cubic(&b_points);
+
+    println!();
+    println!(
+        "pub const AS: [f64; 4] = {};",
+        format_coefficients(&fitted_as)
+    );
+    println!(
+        "pub const BS: [f64; 4] = {};",
+        format_coefficients(&fitted_bs)
+    );
+
+    Ok(())
+}
+
+fn read_datagen(path: &str) -> io::Result<(Vec<Sample>, u64)> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut samples = Vec::new();
+    let mut skipped_lines = 0;
+
+    for line in reader.lines() {
+        let line = line?;
+        let Some((fen, eval, result)) = parse_line(&line) else {
+            skipped_lines += 1;
+            continue;
+        };
+        let Ok(board) = Board::try_from_fen(fen) else {
+            skipped_lines += 1;
+            continue;
+        };
+
+        // older files without a move number are taken to be inside the fitted range
+        let full_move = fen
+            .split_whitespace()
+            .nth(5)
+            .and_then(|full_move| full_move.parse().ok())
+            .unwrap_or(MIN_FULL_MOVE);
+
+        samples.push(Sample {
+            material: wdl::material(&board),
+            full_move,
+            eval,
+            result,
+        });
+    }
+
+    Ok((samples, skipped_lines))
+}
+
+// the model clamps anything outside its material range, so those positions can't shape it
+fn bucket_samples(samples: &[Sample]) -> (BTreeMap<i32, EvalOutcomes>, u64) {
+    let mut buckets: BTreeMap<i32, EvalOutcomes> = BTreeMap::new();
+    let mut left_out = 0;
+
+    for sample in samples {
+        let in_range = (wdl::MIN_MATERIAL..=wdl::MAX_MATERIAL).contains(&sample.material)
+            && (MIN_FULL_MOVE..=MAX_FULL_MOVE).contains(&sample.full_move);
+        if !in_range {
+            left_out += 1;
+            continue;
+        }
+
+        let outcomes = buckets.entry(sample.material).or_default();
+
+        // every position counts for both sides so the fit comes out symmetric
+        let eval = sample.eval.clamp(-MAX_WDL_EVAL, MAX_WDL_EVAL);
+        outcomes.entry(eval).or_default()[sample.result] += 1;
+        outcomes.entry(-eval).or_default()[LOSS - sample.result] += 1;
+    }
+
+    (buckets, left_out)
+}
+
+fn parse_line(line: &str) -> Option<(&str, i16, usize)> {
+    let mut sections = line.split('|').map(str::trim);
+
+    let fen = sections.next().filter(|fen| !fen.is_empty())?;
+    let eval = sections.next()?.parse::<i16>().ok()?;
+    let result = sections.next()?.trim_matches(['[', ']']);
+
+    let result = match result.parse::<f32>().ok()? {
+        1.0 => WIN,
+        0.5 => DRAW,
+        0.0 => LOSS,
+        _ => return None,
+    };
+
+    Some((fen, eval, result))
+}
+
+fn read_pgn(path: &str) -> io::Result<(Vec<Sample>, u64)> {
+    let pgn = fs::read_to_string(path)?;
+
+    let mut samples = Vec::new();
+    let mut skipped_games = 0;
+
+    for game in split_games(&pgn) {
+        match read_game(&game) {
+            Some(game_samples) => samples.extend(game_samples),
+            None => skipped_games += 1,
+        }
+    }
+
+    Ok((samples, skipped_games))
+}
+
+// a game is its tag lines followed by the movetext, the next tag line after movetext starts a new one
+fn split_games(pgn: &str) -> Vec<String> {
+    let mut games = Vec::new();
+    let mut game = String::new();
+    let mut in_movetext = false;
+
+    for line in pgn.lines() {
+        let is_tag = line.trim_start().starts_with('[');
+        if is_tag && in_movetext {
+            games.push(std::mem::take(&mut game));
+            in_movetext = false;
+        }
+
+        in_movetext |= !is_tag && !line.trim().is_empty();
+        game.push_str(line);
+        game.push('\n');
+    }
+
+    if in_movetext {
+        games.push(game);
+    }
+
+    games
+}
+
+fn tag_value<'a>(game: &'a str, name: &str) -> Option<&'a str> {
+    game.lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix('[')?.strip_suffix(']'))
+        .find_map(|tag| {
+            let (tag_name, value) = tag.split_once(' ')?;
+            (tag_name == name).then(|| value.trim().trim_matches('"'))
+        })
+}
+
+// unfinished games and moves that don't fit the position throw out the whole game
+fn read_game(game: &str) -> Option<Vec<Sample>> {
+    let result = match tag_value(game, "Result")? {
+        "1-0" => WIN,
+        "1/2-1/2" => DRAW,
+        "0-1" => LOSS,
+        _ => return None,
+    };
+
+    let fen = tag_value(game, "FEN").unwrap_or(START_POS);
+    let mut board = Board::try_from_fen(fen).ok()?;
+    let mut full_move = fen.split_whitespace().nth(5)?.parse::<u32>().ok()?;
+
+    let movetext: String = game
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('['))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut samples = Vec::new();
+
+    for (san_move, comment) in parse_movetext(&movetext) {
+        let played = find_san_move(&board, &san_move)?;
+
+        // book moves, mates and comments that aren't evals don't make a sample
+        if let Some(eval) = comment.as_deref().and_then(parse_comment_eval)
+            && !board.in_check()
+        {
+            let eval = wdl::unnormalize(eval);
+            let white_eval = match board.side_to_move() {
+                Color::White => eval,
+                Color::Black => -eval,
+            };
+
+            samples.push(Sample {
+                material: wdl::material(&board),
+                full_move,
+                eval: white_eval,
+                result,
+            });
+        }
+
+        if board.side_to_move() == Color::Black {
+            full_move += 1;
+        }
+        board.play(&played);
+    }
+
+    Some(samples)
+}
+
+// every move with the comment that follows it, move numbers, nags, variations and the result are dropped
+fn parse_movetext(movetext: &str) -> Vec<(String, Option<String>)> {
+    let mut moves: Vec<(String, Option<String>)> = Vec::new();
+    let mut chars = movetext.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                if let Some(last_move) = moves.last_mut() {
+                    last_move.1 = Some(comment);
+                }
+            }
+            ';' => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            '(' => {
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut token = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "{;(".contains(next) {
+                        break;
+                    }
+                    token.push(next);
+                    chars.next();
+                }
+
+                if matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*")
+                    || token.starts_with('$')
+                {
+                    continue;
+                }
+
+                // move numbers can be glued to the move, as in 12.e4 or 12...e5
+                let san_move = match token.rfind('.') {
+                    Some(dot) if token.starts_with(|c: char| c.is_ascii_digit()) => {
+                        token[dot + 1..].to_string()
+                    }
+                    _ => token,
+                };
+
+                if !san_move.is_empty() {
+                    moves.push((san_move, None));
+                }
+            }
+        }
+    }
+
+    moves
+}
+
+fn find_san_move(board: &Board, san_move: &str) -> Option<MovePly> {
+    let strip = |san_move: &str| {
+        san_move
+            .trim_end_matches(['+', '#', '!', '?'])
+            .replace('0', "O")
+    };
+    let wanted = strip(san_move);
+
+    move_generator::legal_moves(board)
+        .into_iter()
+        .find(|&legal| strip(&san::to_san(board, &legal)) == wanted)
+}
+
+// cutechess writes the score of the side that moved in pawns followed by the depth, mates as +M5
+fn parse_comment_eval(comment: &str) -> Option<i16> {
+    let (score, _) = comment.split_whitespace().next()?.split_once('/')?;
+    let pawns = score.parse::<f64>().ok()?;
+
+    Some(
+        (pawns * 100.0)
+            .round()
+            .clamp(i16::MIN as f64, i16::MAX as f64) as i16,
+    )
+}
+
+fn polynomial_guess(m: f64) -> (f64, f64) {
+    (wdl::polynomial(&AS, m), wdl::polynomial(&BS, m))
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn negative_log_likelihood(outcomes: &EvalOutcomes, a: f64, b: f64) -> f64 {
+    let mut total = 0.0;
+
+    for (&eval, counts) in outcomes {
+        let eval = eval as f64;
+        let win = sigmoid((eval - a) / b).max(1e-9);
+        let loss = sigmoid((-eval - a) / b).max(1e-9);
+        let draw = (1.0 - win - loss).max(1e-9);
+
+        total -= counts[WIN] as f64 * win.ln()
+            + counts[DRAW] as f64 * draw.ln()
+            + counts[LOSS] as f64 * loss.ln();
+    }
+
+    total
+}
+
+// a plain pattern search, two parameters and a smooth error don't need anything fancier
+fn fit_bucket(outcomes: &EvalOutcomes, (mut a, mut b): (f64, f64)) -> (f64, f64) {
+    b = b.max(1.0);
+    let mut best_error = negative_log_likelihood(outcomes, a, b);
+    let mut step = 16.0;
+
+    while step > MIN_STEP {
+        let mut improved = false;
+
+        for (da, db) in [(step, 0.0), (-step, 0.0), (0.0, step), (0.0, -step)] {
+            let (new_a, new_b) = (a + da, b + db);
+            if new_b < 1.0 {
+                continue;
+            }
+
+            let error = negative_log_likelihood(outcomes, new_a, new_b);
+            if error < best_error {
+                (a, b, best_error) = (new_a, new_b, error);
+                improved = true;
+            }
+        }
+
+        if !improved {
+            step /= 2.0;
+        }
+    }
+
+    (a, b)
+}
+
+// weighted least squares through the normal equations, solved with gaussian elimination
+fn fit_cubic(points: &[(f64, f64, f64)]) -> [f64; 4] {
+    let mut matrix = [[0.0; 5]; 4];
+
+    for &(x, y, weight) in points {
+        let powers = [x * x * x, x * x, x, 1.0];
+
+        for row in 0..4 {
+            for col in 0..4 {
+                matrix[row][col] += weight * powers[row] * powers[col];
+            }
+            matrix[row][4] += weight * powers[row] * y;
+        }
+    }
+
+    for pivot in 0..4 {
+        let best_row = (pivot..4)
+            .max_by(|&i, &j| matrix[i][pivot].abs().total_cmp(&matrix[j][pivot].abs()))
+            .unwrap();
+        matrix.swap(pivot, best_row);
+
+        for row in 0..4 {
+            if row == pivot {
+                continue;
+            }
+
+            let pivot_row = matrix[pivot];
+            let factor = matrix[row][pivot] / pivot_row[pivot];
+            for (value, pivot_value) in matrix[row].iter_mut().zip(pivot_row).skip(pivot) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+
+    [0, 1, 2, 3].map(|row| matrix[row][4] / matrix[row][row])
+}
+
+fn format_coefficients(coefficients: &[f64; 4]) -> String {
+    let formatted: Vec<String> = coefficients.iter().map(|c| format!("{c:.2}")).collect();
+    format!("[{}]", formatted.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 40";
+
+        assert_eq!(
+            parse_line(&format!("{fen} | 35 | 1.0")),
+            Some((fen, 35, WIN))
+        );
+        assert_eq!(
+            parse_line(&format!("{fen} | -120 | [0.5]")),
+            Some((fen, -120, DRAW))
+        );
+        assert_eq!(parse_line(&format!("{fen}|0|0")), Some((fen, 0, LOSS)));
+
+        assert_eq!(parse_line(&format!("{fen} | 35 | 2.0")), None);
+        assert_eq!(parse_line(&format!("{fen} | +M3 | 1.0")), None);
+        assert_eq!(parse_line(&format!("{fen} | 35")), None);
+        assert_eq!(parse_line(" | 35 | 1.0"), None);
+        assert_eq!(parse_line(""), None);
+    }
+
+    #[test]
+    fn test_fit_cubic_recovers_a_cubic() {
+        let cubic = [2.0, -3.0, 4.0, 5.0];
+        let points: Vec<_> = (0..12)
+            .map(|i| {
+                let x = 0.25 + i as f64 * 0.1;
+                (x, wdl::polynomial(&cubic, x), 1.0 + i as f64)
+            })
+            .collect();
+
+        let fitted = fit_cubic(&points);
+        for (fitted, expected) in fitted.iter().zip(cubic) {
+            assert!((fitted - expected).abs() < 1e-6, "{fitted:?} {cubic:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_comment_eval() {
+        assert_eq!(parse_comment_eval("+0.35/12 0.51s"), Some(35));
+        assert_eq!(parse_comment_eval("-1.20/15 1.02s"), Some(-120));
+        assert_eq!(parse_comment_eval("+M5/20 0.10s"), None);
+        assert_eq!(parse_comment_eval("book"), None);
+        assert_eq!(parse_comment_eval("a nice move"), None);
+    }
+
+    #[test]
+    fn test_pgn_games_turn_into_samples() {
+        let pgn = "[Event \"test\"]\n[Result \"0-1\"]\n\n\
+                   1. e4 {book} e5 {book} 2. Nf3 {+0.30/10 0.1s} Nc6 {-0.20/10 0.1s}\n\
+                   3. Bb5 {+0.25/11 0.1s} (3. Bc4 Bc5) a6 $1 {+0.10/9 0.1s} 0-1\n\n\
+                   [Event \"unfinished\"]\n[Result \"*\"]\n\n1. d4 {+0.20/10 0.1s} *\n\n\
+                   [Event \"illegal\"]\n[Result \"1-0\"]\n\n1. e5 {+0.20/10 0.1s} 1-0\n";
+
+        let games = split_games(pgn);
+        assert_eq!(games.len(), 3);
+        assert!(read_game(&games[1]).is_none());
+        assert!(read_game(&games[2]).is_none());
+
+        let samples = read_game(&games[0]).unwrap();
+        let evals: Vec<i16> = samples.iter().map(|sample| sample.eval).collect();
+        let expected: Vec<i16> = [30, 20, 25, -10].map(wdl::unnormalize).to_vec();
+        assert_eq!(evals, expected);
+
+        let full_moves: Vec<u32> = samples.iter().map(|sample| sample.full_move).collect();
+        assert_eq!(full_moves, vec![2, 2, 3, 3]);
+        assert!(samples.iter().all(|sample| sample.result == LOSS));
+        assert!(samples.iter().all(|sample| sample.material == 78));
+    }
+}
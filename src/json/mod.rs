@@ -0,0 +1,3 @@
+pub mod reporter;
+pub mod session;
+pub mod value;
@@ -0,0 +1,113 @@
+use crate::chess::board::Board;
+use crate::chess::san;
+use crate::engine::info::{Bound, IterationInfo};
+use crate::engine::reporter::SearchReporter;
+use crate::engine::score::{self, ReportedScore};
+use crate::engine::thread_pool::ThreadResult;
+use crate::json::value::JsonValue;
+
+// the score is either {"cp": n} on the normalized scale or {"mate": n} in full moves like uci
+pub fn json_score(score: i16) -> JsonValue {
+    match score::to_reported(score) {
+        ReportedScore::Cp(cp) => JsonValue::object([("cp", JsonValue::from(cp as i64))]),
+        ReportedScore::Mate(moves) => JsonValue::object([("mate", JsonValue::from(moves as i64))]),
+    }
+}
+
+// made for every search so the bestmove event can carry the id of the request
+pub struct JsonReporter {
+    pub id: JsonValue,
+}
+
+impl JsonReporter {
+    fn print_iteration(&self, board: &Board, info: &IterationInfo) {
+        let bound = match info.bound {
+            Bound::Exact => "exact",
+            Bound::Lower => "lower",
+            Bound::Upper => "upper",
+        };
+
+        let (win, draw, loss) = info.wdl;
+        let pv_uci = info
+            .pv
+            .iter()
+            .map(|pv_move| JsonValue::from(pv_move.to_string()));
+        let pv_san = san::line_to_san(board, info.pv)
+            .into_iter()
+            .map(JsonValue::from);
+
+        let event = JsonValue::object([
+            ("event", JsonValue::from("iteration")),
+            ("id", self.id.clone()),
+            ("depth", JsonValue::from(info.depth as i64)),
+            ("seldepth", JsonValue::from(info.sel_depth as i64)),
+            ("multipv", JsonValue::from(info.pv_idx as i64 + 1)),
+            ("score", json_score(info.score)),
+            ("bound", JsonValue::from(bound)),
+            (
+                "wdl",
+                JsonValue::Array(vec![
+                    JsonValue::from(win as i64),
+                    JsonValue::from(draw as i64),
+                    JsonValue::from(loss as i64),
+                ]),
+            ),
+            ("nodes", JsonValue::from(info.nodes as i64)),
+            ("nps", JsonValue::from(info.nps as i64)),
+            ("time", JsonValue::from(info.time as i64)),
+            ("hashfull", JsonValue::from(info.hash_full as i64)),
+            ("pv", JsonValue::Array(pv_uci.collect())),
+            ("pv_san", JsonValue::Array(pv_san.collect())),
+        ]);
+
+        println!("{event}");
+    }
+}
+
+impl SearchReporter for JsonReporter {
+    fn iteration(&self, board: &Board, info: &IterationInfo) {
+        self.print_iteration(board, info);
+    }
+
+    fn bound_update(&self, board: &Board, info: &IterationInfo) {
+        self.print_iteration(board, info);
+    }
+
+    fn best_move(&self, board: &Board, result: &ThreadResult) {
+        // mated or stalemated positions have no move to play
+        let (best_move, best_move_san) = if result.best_move.is_default() {
+            (JsonValue::Null, JsonValue::Null)
+        } else {
+            (
+                JsonValue::from(result.best_move.to_string()),
+                JsonValue::from(san::to_san(board, &result.best_move)),
+            )
+        };
+
+        let ponder_san = result.ponder_move.map(|ponder_move| {
+            let mut board = board.clone();
+            board.make_move::<true>(&result.best_move);
+            san::to_san(&board, &ponder_move)
+        });
+
+        let event = JsonValue::object([
+            ("event", JsonValue::from("bestmove")),
+            ("id", self.id.clone()),
+            ("move", best_move),
+            ("san", best_move_san),
+            (
+                "ponder",
+                JsonValue::from(
+                    result
+                        .ponder_move
+                        .map(|ponder_move| ponder_move.to_string()),
+                ),
+            ),
+            ("ponder_san", JsonValue::from(ponder_san)),
+            ("score", json_score(result.score)),
+            ("depth", JsonValue::from(result.depth as i64)),
+        ]);
+
+        println!("{event}");
+    }
+}
@@ -0,0 +1,276 @@
+use crate::chess::board::Board;
+use crate::chess::move_ply;
+use crate::engine::reporter::SearchReporter;
+use crate::engine::search_limits::{MAX_SEARCH_DEPTH, SearchLimits};
+use crate::engine::skill::Skill;
+use crate::engine::thread_pool::ThreadPool;
+use crate::engine::time_manager::{TimeControl, TimeManager};
+use crate::engine::transposition::Transposition;
+use crate::json::reporter::JsonReporter;
+use crate::json::value::JsonValue;
+use std::sync::Arc;
+
+const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const MOVE_OVERHEAD: u32 = 20;
+
+// one json object per line in each direction
+//
+// requests:
+//   {"type": "search", "id": 1, "fen": "...", "moves": ["e2e4"], "multipv": 2,
+//    "limits": {"depth": 12, "nodes": 100000, "movetime": 1000,
+//               "wtime": 60000, "btime": 60000, "winc": 0, "binc": 0, "movestogo": 40}}
+//   {"type": "new_game"}
+//   {"type": "quit"}
+// fen defaults to the start position, every limit is optional and without any a search takes a second
+// while a depth or node limit without a clock searches until it is reached
+//
+// events:
+//   {"event": "iteration", "id": 1, ...} for every finished line and every aspiration fail, see JsonReporter
+//   {"event": "bestmove", "id": 1, "move": "e2e4", "san": "e4", "ponder": "e7e5", "ponder_san": "e5",
+//    "score": {"cp": 30}, "depth": 12}
+//   {"event": "error", "id": 1, "message": "..."}
+pub struct JsonSession {
+    tt: Arc<Transposition>,
+    num_threads: usize,
+    thread_pool: ThreadPool,
+}
+
+struct SearchRequest {
+    board: Board,
+    search_limits: SearchLimits,
+}
+
+impl JsonSession {
+    pub fn new(tt_size: u16, num_threads: usize) -> Self {
+        Self {
+            tt: Arc::new(Transposition::new(tt_size, num_threads)),
+            num_threads,
+            thread_pool: ThreadPool::new(num_threads),
+        }
+    }
+
+    pub fn run(&mut self) {
+        println!(
+            "{}",
+            JsonValue::object([("event", JsonValue::from("ready"))])
+        );
+
+        loop {
+            let mut input = String::new();
+            let bytes_read = std::io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read input line.");
+
+            if bytes_read == 0 {
+                return;
+            }
+
+            if input.trim().is_empty() {
+                continue;
+            }
+
+            let request = match JsonValue::parse(input.trim()) {
+                Ok(request) => request,
+                Err(message) => {
+                    Self::print_error(&JsonValue::Null, &format!("invalid json: {message}"));
+                    continue;
+                }
+            };
+
+            if !self.handle(&request) {
+                return;
+            }
+        }
+    }
+
+    fn print_error(id: &JsonValue, message: &str) {
+        let event = JsonValue::object([
+            ("event", JsonValue::from("error")),
+            ("id", id.clone()),
+            ("message", JsonValue::from(message)),
+        ]);
+
+        println!("{event}");
+    }
+
+    // returns false once the client wants the engine gone
+    fn handle(&mut self, request: &JsonValue) -> bool {
+        let id = request.get("id").cloned().unwrap_or(JsonValue::Null);
+
+        match request.get("type").and_then(JsonValue::as_str) {
+            Some("search") => match Self::parse_search(request) {
+                Ok(search_request) => self.search(&id, &search_request),
+                Err(message) => Self::print_error(&id, &message),
+            },
+            Some("new_game") => {
+                self.tt.clear(self.num_threads);
+                self.thread_pool.new_game();
+            }
+            Some("quit") => return false,
+            Some(request_type) => {
+                Self::print_error(&id, &format!("unknown request type '{request_type}'"))
+            }
+            None => Self::print_error(&id, "request has no type"),
+        }
+
+        true
+    }
+
+    fn parse_search(request: &JsonValue) -> Result<SearchRequest, String> {
+        let fen = match request.get("fen") {
+            Some(fen) => fen.as_str().ok_or("fen must be a string")?,
+            None => START_POS,
+        };
+
+        let mut board = Board::try_from_fen(fen)?;
+
+        if let Some(moves) = request.get("moves") {
+            let moves = moves.as_array().ok_or("moves must be an array")?;
+
+            for played in moves {
+                let played = played.as_str().ok_or("moves must be strings")?;
+                let legal_move = move_ply::find_legal_move(played, &board)
+                    .ok_or(format!("illegal move '{played}'"))?;
+
+                board.play(&legal_move);
+            }
+        }
+
+        let multi_pv = match request.get("multipv") {
+            Some(multi_pv) => multi_pv
+                .as_u64()
+                .filter(|multi_pv| *multi_pv >= 1)
+                .ok_or("multipv must be a positive integer")?
+                as usize,
+            None => 1,
+        };
+
+        let empty_limits = JsonValue::Object(Vec::new());
+        let limits = request.get("limits").unwrap_or(&empty_limits);
+        if !matches!(limits, JsonValue::Object(_)) {
+            return Err(String::from("limits must be an object"));
+        }
+
+        let limit = |name: &str| -> Result<Option<u64>, String> {
+            match limits.get(name) {
+                Some(value) => value
+                    .as_u64()
+                    .map(Some)
+                    .ok_or(format!("limit '{name}' must be a non-negative integer")),
+                None => Ok(None),
+            }
+        };
+        let time_limit = |name: &str| -> Result<Option<u32>, String> {
+            Ok(limit(name)?.map(|value| value.min(u32::MAX as u64) as u32))
+        };
+
+        let time_control = TimeControl {
+            move_overhead: MOVE_OVERHEAD,
+            ..TimeControl::from_go(
+                board.side_to_move(),
+                time_limit("movetime")?,
+                time_limit("wtime")?,
+                time_limit("btime")?,
+                time_limit("winc")?,
+                time_limit("binc")?,
+                time_limit("movestogo")?,
+            )
+        };
+
+        let depth = limit("depth")?;
+        let nodes = limit("nodes")?;
+
+        // a depth or node limit on its own shouldn't get cut short by the default think time
+        let has_clock = time_control.move_time.is_some() || time_control.time_left.is_some();
+        let time_manager = if !has_clock && (depth.is_some() || nodes.is_some()) {
+            TimeManager::infinite()
+        } else {
+            TimeManager::new(&time_control)
+        };
+
+        let mut search_limits = SearchLimits::new(time_manager, multi_pv, Skill::default());
+
+        if let Some(depth) = depth {
+            search_limits = search_limits.with_max_depth(depth.min(MAX_SEARCH_DEPTH as u64) as u8);
+        }
+        if let Some(nodes) = nodes {
+            search_limits = search_limits.with_node_limit(nodes);
+        }
+
+        Ok(SearchRequest {
+            board,
+            search_limits,
+        })
+    }
+
+    fn search(&self, id: &JsonValue, request: &SearchRequest) {
+        let reporter: Arc<dyn SearchReporter> = Arc::new(JsonReporter { id: id.clone() });
+
+        self.thread_pool
+            .search_start(&self.tt, &request.board, &request.search_limits, &reporter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_search(request: &str) -> Result<SearchRequest, String> {
+        JsonSession::parse_search(&JsonValue::parse(request).unwrap())
+    }
+
+    #[test]
+    fn test_parse_search_reads_the_position_and_limits() {
+        let Ok(request) = parse_search(
+            r#"{"type":"search","moves":["e2e4","e7e5"],"multipv":3,"limits":{"depth":6,"nodes":1000}}"#,
+        ) else {
+            panic!("a valid request was rejected");
+        };
+
+        assert_eq!(
+            request.board.to_fen(2),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        assert_eq!(request.search_limits.multi_pv(), 3);
+        assert_eq!(request.search_limits.max_depth(), 6);
+        assert!(request.search_limits.is_node_limit_reached(1000));
+        assert!(!request.search_limits.is_node_limit_reached(999));
+    }
+
+    #[test]
+    fn test_parse_search_errors() {
+        for (request, error) in [
+            (r#"{"fen":1}"#, "fen must be a string"),
+            (r#"{"moves":"e2e4"}"#, "moves must be an array"),
+            (r#"{"moves":[1]}"#, "moves must be strings"),
+            (r#"{"moves":["e2e5"]}"#, "illegal move 'e2e5'"),
+            (r#"{"multipv":0}"#, "multipv must be a positive integer"),
+            (r#"{"multipv":1.5}"#, "multipv must be a positive integer"),
+            (r#"{"limits":[]}"#, "limits must be an object"),
+            (
+                r#"{"limits":{"depth":-1}}"#,
+                "limit 'depth' must be a non-negative integer",
+            ),
+            (
+                r#"{"limits":{"movetime":"1s"}}"#,
+                "limit 'movetime' must be a non-negative integer",
+            ),
+        ] {
+            assert_eq!(parse_search(request).err().as_deref(), Some(error));
+        }
+    }
+
+    #[test]
+    fn test_parse_search_rejects_bad_fens() {
+        for fen in [
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "",
+        ] {
+            let request = format!(r#"{{"fen":"{fen}"}}"#);
+            assert!(parse_search(&request).is_err(), "{fen:?} was accepted");
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use std::fmt::{Display, Formatter};
+
+// arrays and objects are parsed recursively, anything nested deeper than this would only run the stack out
+const MAX_NESTING_DEPTH: usize = 64;
+
+// just enough json for the analysis protocol, objects keep their keys in the order they were written
+#[derive(Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        JsonValue::Bool(value)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(value: i64) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl JsonValue {
+    pub fn object<const N: usize>(fields: [(&str, JsonValue); N]) -> Self {
+        JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn parse(text: &str) -> Result<JsonValue, String> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+            depth: 0,
+        };
+
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+
+        if parser.position != parser.chars.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields
+                .iter()
+                .find(|(field_key, _)| field_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(value) if *value >= 0.0 && value.fract() == 0.0 => {
+                Some(*value as u64)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter, value: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+
+    for char in value.chars() {
+        match char {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            char if (char as u32) < 0x20 => write!(f, "\\u{:04x}", char as u32)?,
+            char => write!(f, "{char}")?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+// written on a single line, one event per line is what the protocol relies on
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            JsonValue::Null => write!(f, "null"),
+            JsonValue::Bool(value) => write!(f, "{value}"),
+            JsonValue::Number(value) if !value.is_finite() => write!(f, "null"),
+            JsonValue::Number(value) => write!(f, "{value}"),
+            JsonValue::String(value) => write_string(f, value),
+            JsonValue::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            JsonValue::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    // how many arrays and objects the current value is inside of
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!("{message} at character {}", self.position)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.peek();
+        self.position += 1;
+        char
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(char) if char == expected => Ok(()),
+            _ => Err(self.error(&format!("expected '{expected}'"))),
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, String> {
+        for expected in literal.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.parse_nested(Self::parse_object),
+            Some('[') => self.parse_nested(Self::parse_array),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some('t') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some('n') => self.parse_literal("null", JsonValue::Null),
+            Some(char) if char == '-' || char.is_ascii_digit() => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<JsonValue, String>,
+    ) -> Result<JsonValue, String> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.parse_value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(values)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    string.push(escaped);
+                }
+                Some(char) => string.push(char),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_hex_code(&mut self) -> Result<u32, String> {
+        let end = self.position + 4;
+        if end > self.chars.len() {
+            return Err(self.error("incomplete unicode escape"));
+        }
+
+        let hex: String = self.chars[self.position..end].iter().collect();
+        let code =
+            u32::from_str_radix(&hex, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.position = end;
+
+        Ok(code)
+    }
+
+    // characters outside the basic plane come as a high surrogate escape followed by a low one
+    // a surrogate without its other half becomes a replacement char
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let code = self.parse_hex_code()?;
+
+        if (0xd800..0xdc00).contains(&code) && self.chars[self.position..].starts_with(&['\\', 'u'])
+        {
+            let after_high = self.position;
+            self.position += 2;
+            let low = self.parse_hex_code()?;
+
+            if (0xdc00..0xe000).contains(&low) {
+                let combined = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                return Ok(char::from_u32(combined).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+
+            // not a low surrogate, it is read again as a char of its own
+            self.position = after_high;
+        }
+
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String> {
+        let start = self.position;
+
+        while self.peek().is_some_and(|char| {
+            char.is_ascii_digit() || matches!(char, '-' | '+' | '.' | 'e' | 'E')
+        }) {
+            self.position += 1;
+        }
+
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| self.error("invalid number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> String {
+        JsonValue::parse(text).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for text in [
+            "null",
+            "true",
+            "[]",
+            "{}",
+            "[1,-2.5,\"a\",null,[false]]",
+            r#"{"type":"search","id":1,"limits":{"depth":12},"moves":["e2e4","e7e5"]}"#,
+        ] {
+            assert_eq!(round_trip(text), text);
+        }
+
+        assert_eq!(round_trip(" { \"a\" : [ 1 , 2 ] } "), r#"{"a":[1,2]}"#);
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let parsed = JsonValue::parse(r#""q\"b\\s\/n\nt\tu\u00e9""#).unwrap();
+        assert_eq!(parsed.as_str(), Some("q\"b\\s/n\nt\tu\u{e9}"));
+        assert_eq!(parsed.to_string(), "\"q\\\"b\\\\s/n\\nt\\tu\u{e9}\"");
+
+        assert_eq!(round_trip(r#""\u0001""#), r#""\u0001""#);
+    }
+
+    #[test]
+    fn test_unicode_surrogates() {
+        let parsed = |text| {
+            JsonValue::parse(text)
+                .unwrap()
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(parsed(r#""\ud83d\ude00""#), "\u{1f600}");
+        assert_eq!(parsed(r#""\ud83dx""#), "\u{fffd}x");
+        assert_eq!(parsed(r#""\ude00""#), "\u{fffd}");
+        assert_eq!(parsed(r#""\ud83d\u0041""#), "\u{fffd}A");
+    }
+
+    #[test]
+    fn test_numbers() {
+        let number = |text| match JsonValue::parse(text) {
+            Ok(JsonValue::Number(value)) => value,
+            _ => panic!("{text} is not a number"),
+        };
+
+        assert_eq!(number("0"), 0.0);
+        assert_eq!(number("-17"), -17.0);
+        assert_eq!(number("2.5e3"), 2500.0);
+        assert_eq!(number("1E-2"), 0.01);
+
+        assert_eq!(JsonValue::parse("12").unwrap().as_u64(), Some(12));
+        assert_eq!(JsonValue::parse("1.5").unwrap().as_u64(), None);
+        assert_eq!(JsonValue::parse("-1").unwrap().as_u64(), None);
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        for text in [
+            "",
+            "   ",
+            "[1,2",
+            "[1 2]",
+            "{\"a\" 1}",
+            "{\"a\":1,}",
+            "{a:1}",
+            "\"open",
+            "\"\\x\"",
+            "\"\\u12\"",
+            "\"\\uzzzz\"",
+            "tru",
+            "nul",
+            "1-2",
+            "-",
+            "1 2",
+            "+1",
+        ] {
+            assert!(JsonValue::parse(text).is_err(), "{text:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth_is_limited() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+
+        assert!(JsonValue::parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert!(JsonValue::parse(&nested(MAX_NESTING_DEPTH + 1)).is_err());
+        assert!(JsonValue::parse(&"[{\"a\":".repeat(100_000)).is_err());
+    }
+}
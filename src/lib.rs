@@ -0,0 +1,44 @@
+//! Board representation, move generation, search and perft of the engine, usable without the
+//! uci binary. The everyday types are re-exported here.
+//!
+//! ```
+//! use chess_engine::{Board, legal_moves};
+//!
+//! let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+//! assert_eq!(legal_moves(&board).len(), 20);
+//! ```
+//!
+//! A search runs on a [`ThreadPool`] and tells a [`SearchReporter`] about its progress:
+//!
+//! ```
+//! use chess_engine::{Board, SearchLimits, SearchReporter, SilentReporter, ThreadPool};
+//! use chess_engine::{Transposition, engine::skill::Skill, engine::time_manager::TimeManager};
+//! use std::sync::Arc;
+//!
+//! let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+//! let tt = Arc::new(Transposition::new(16, 1));
+//! let limits = SearchLimits::new(TimeManager::infinite(), 1, Skill::default()).with_max_depth(8);
+//! let reporter: Arc<dyn SearchReporter> = Arc::new(SilentReporter);
+//!
+//! let best_move = ThreadPool::new(1).search_start(&tt, &board, &limits, &reporter);
+//! assert_eq!(best_move.to_string(), "a1a8");
+//! ```
+
+pub mod chess;
+pub mod engine;
+pub mod general;
+pub mod json;
+pub mod precomputed;
+pub mod uci;
+pub mod xboard;
+
+pub use chess::board::Board;
+pub use chess::move_generator::{MoveGenerator, legal_moves};
+pub use chess::move_list::MoveList;
+pub use chess::move_ply::MovePly;
+pub use engine::perft::{BULK_PERFT, PERFT, TT_PERFT, perft};
+pub use engine::reporter::{CollectingReporter, SearchReporter, SilentReporter, UciReporter};
+pub use engine::search::Searcher;
+pub use engine::search_limits::SearchLimits;
+pub use engine::thread_pool::ThreadPool;
+pub use engine::transposition::Transposition;
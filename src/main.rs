@@ -1,38 +1,28 @@
-use crate::chess::board::Board;
-use crate::chess::move_generator::GEN_ALL;
-use crate::chess::move_generator::MoveGenerator;
-use crate::chess::move_list::MoveList;
-use crate::chess::move_ply;
-use crate::chess::move_ply::MovePly;
-use crate::chess::types::color::Color;
-use crate::engine::arbiter::Arbiter;
-use crate::engine::perft::{BULK_PERFT, PERFT, TT_PERFT, perft};
-use crate::engine::search::Searcher;
-use crate::engine::search_limits::SearchLimits;
-use crate::engine::transposition::Transposition;
-use crate::engine::types::match_result::MatchResult;
-use crate::uci::commands::{Commands, OptionsType};
-use crate::uci::option_table::print_option_table;
-use crate::uci::parser;
+use chess_engine::chess::board::Board;
+use chess_engine::chess::move_generator::GEN_ALL;
+use chess_engine::chess::move_generator::MoveGenerator;
+use chess_engine::chess::move_list::MoveList;
+use chess_engine::chess::move_ply::MovePly;
+use chess_engine::engine::arbiter::Arbiter;
+use chess_engine::engine::datagen;
+use chess_engine::engine::gauntlet;
+use chess_engine::engine::perft::{BULK_PERFT, perft};
+use chess_engine::engine::reporter::{SearchReporter, UciReporter};
+use chess_engine::engine::search_limits::SearchLimits;
+use chess_engine::engine::skill::Skill;
+use chess_engine::engine::thread_pool::ThreadPool;
+use chess_engine::engine::time_manager::{TimeControl, TimeManager};
+use chess_engine::engine::transposition::Transposition;
+use chess_engine::engine::types::match_result::MatchResult;
+use chess_engine::engine::wdl_fit;
+use chess_engine::json::session::JsonSession;
+use chess_engine::uci::session::UciSession;
+use chess_engine::xboard::session::XBoardSession;
 use std::env;
 use std::fs::File;
 use std::io::Read;
-use std::process::exit;
 use std::sync::Arc;
 
-mod chess;
-mod engine;
-mod general;
-pub mod precomputed;
-mod uci;
-
-const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-
-const HELP_MSG: &str = "\nA fairly generic rust engine supporting the UCI protocol.\n\
-Commands are the same as the uci protocol, except perft which can be called by perft <depth>\n\
-Some UCI features are yet to be implemented.\n\
-";
-
 const AUTHOR: &str = "Harry Phillips";
 const NAME: &str = "Generic Rust UCI Engine";
 
@@ -50,142 +40,67 @@ fn main() {
         if args[1] == "pgo-perft" {
             run_perft_pgo();
         }
-        return;
-    }
-
-    println!("{NAME} by {AUTHOR}\n");
-
-    let mut current_fen: String = START_POS.to_string();
-    let mut board = Board::default();
-
-    board.new(&current_fen);
-
-    let mut tt_size = 64;
-    let mut tt = Arc::new(Transposition::new(tt_size));
-
-    let mut num_threads = 1;
 
-    loop {
-        let mut input: String = String::new();
-        std::io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read input line.");
-
-        let uci_command = parser::UCIParser::parse(&input);
-        match uci_command {
-            Commands::Uci => {
-                println!("id name {NAME}\nid author {AUTHOR}\n");
-                print_option_table();
-                println!("uciok");
-            }
-            Commands::IsReady => println!("readyok"),
-            Commands::Quit => exit(1),
-
-            Commands::Help => println!("{}", HELP_MSG),
-
-            Commands::UciNewGame => {
-                tt = Arc::new(Transposition::new(tt_size));
-                current_fen = START_POS.to_string();
-                board.new(&current_fen);
-            }
-
-            Commands::Perft { depth } => {
-                perft::<PERFT>(&mut board, depth as u8);
-            }
+        if args[1] == "--json" {
+            JsonSession::new(64, 1).run();
+        }
 
-            Commands::TTPerft { depth } => {
-                perft::<TT_PERFT>(&mut board, depth as u8);
-            }
+        if args[1] == "datagen" {
+            let seed = args.get(2).and_then(|seed| seed.parse().ok());
+            let num_games = args.get(3).and_then(|num_games| num_games.parse().ok());
+            let nodes = args.get(4).and_then(|nodes| nodes.parse().ok());
 
-            Commands::BulkPerft { depth } => {
-                perft::<BULK_PERFT>(&mut board, depth as u8);
-            }
-            Commands::Position { fen, moves } => {
-                current_fen = fen;
-                board = Board::default();
-                board.new(&current_fen);
-                if let Some(str_moves) = moves {
-                    for str_move in str_moves {
-                        board.make_move::<true>(&move_ply::uci_move_parser(&str_move, &board))
+            match (seed, num_games, nodes, args.get(5)) {
+                (Some(seed), Some(num_games), Some(nodes), Some(path)) => {
+                    if let Err(error) = datagen::run(seed, num_games, nodes, path) {
+                        println!("Failed to generate data: {error}");
                     }
                 }
+                _ => println!("Usage: datagen <seed> <games> <nodes per move> <output file>"),
             }
+        }
 
-            Commands::Go {
-                move_time,
-                wtime,
-                btime,
-                winc,
-                binc,
-                moves_to_go,
-            } => {
-                let mut hard_think_time: u32 = 1000;
-                let soft_think_time;
-
-                let mut moves_left: u32 = 20;
-
-                if let Some(moves_to_go) = moves_to_go {
-                    moves_left = moves_to_go;
-                }
+        if args[1] == "gauntlet" {
+            let seed = args.get(2).and_then(|seed| seed.parse().ok());
+            let games_per_pair = args.get(3).and_then(|games| games.parse().ok());
 
-                match board.side_to_move() {
-                    Color::White => {
-                        if let Some(wtime) = wtime {
-                            hard_think_time = wtime / moves_left;
-                        }
-                        if let Some(winc) = winc {
-                            hard_think_time += winc;
-                        }
-                    }
-                    Color::Black => {
-                        if let Some(btime) = btime {
-                            hard_think_time = btime / moves_left;
-                        }
-                        if let Some(binc) = binc {
-                            hard_think_time += binc;
-                        }
+            match (seed, games_per_pair, args.get(4)) {
+                (Some(seed), Some(games_per_pair), Some(path)) => {
+                    if let Err(error) = gauntlet::run(seed, games_per_pair, path) {
+                        println!("Failed to run the gauntlet: {error}");
                     }
                 }
-
-                if let Some(move_time) = move_time {
-                    hard_think_time = move_time.saturating_sub(20).max(5);
-                    soft_think_time = move_time.saturating_sub(20).max(5);
-                } else {
-                    soft_think_time = (hard_think_time as f64 * 0.6f64) as u32;
-                }
-
-                let search_limits = SearchLimits::new(hard_think_time, soft_think_time);
-
-                Searcher::search_start(&tt, &board, &search_limits, num_threads);
+                _ => println!("Usage: gauntlet <seed> <games per pair> <output file>"),
             }
+        }
 
-            Commands::SetOption { options_type } => match options_type {
-                OptionsType::Spin { name, value } => match name.as_str() {
-                    "Threads" => num_threads = value as usize,
-                    "Hash" => {
-                        tt_size = value;
-                        tt = Arc::new(Transposition::new(tt_size));
-                    }
-                    _ => unreachable!(),
-                },
-
-                OptionsType::Button { name } => match name.as_str() {
-                    "Clear Hash" => {
-                        tt = Arc::new(Transposition::new(tt_size));
+        if args[1] == "wdl-fit" {
+            match args.get(2) {
+                Some(path) => {
+                    if let Err(error) = wdl_fit::run(path) {
+                        println!("Failed to fit the wdl model: {error}");
                     }
-                    _ => unreachable!(),
-                },
-            },
-
-            Commands::Unknown(line) => {
-                if line != "\r\n" {
-                    println!("Unknown command: '{line}'. Type help for more information.\n")
                 }
+                None => println!("Usage: wdl-fit <datagen file or .pgn file>"),
             }
-
-            _ => {}
         }
+        return;
     }
+
+    println!("{NAME} by {AUTHOR}\n");
+
+    // the gui gives away which protocol it speaks with its very first command
+    let mut first_input = String::new();
+    std::io::stdin()
+        .read_line(&mut first_input)
+        .expect("Failed to read input line.");
+
+    if first_input.trim() == "xboard" {
+        XBoardSession::new(NAME, 64, 1).run();
+        return;
+    }
+
+    UciSession::new(NAME, AUTHOR).run(&first_input);
 }
 
 /* fn test_code() {
@@ -315,34 +230,33 @@ fn main() {
 pub fn run_perft_pgo() {
     let mut board = Board::default();
     const PERFT_TYPE: u8 = BULK_PERFT;
+    let reporter = UciReporter { show_wdl: false };
 
     board.new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-    perft::<PERFT_TYPE>(&mut board, 7);
+    perft::<PERFT_TYPE>(&mut board, 7, &reporter);
 
     board = Board::default();
     board.new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ");
-    perft::<PERFT_TYPE>(&mut board, 6);
+    perft::<PERFT_TYPE>(&mut board, 6, &reporter);
 
     board = Board::default();
     board.new("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
-    perft::<PERFT_TYPE>(&mut board, 8);
+    perft::<PERFT_TYPE>(&mut board, 8, &reporter);
 
     board = Board::default();
     board.new("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
-    perft::<PERFT_TYPE>(&mut board, 6);
+    perft::<PERFT_TYPE>(&mut board, 6, &reporter);
 
     board = Board::default();
     board.new("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8");
-    perft::<PERFT_TYPE>(&mut board, 6);
+    perft::<PERFT_TYPE>(&mut board, 6, &reporter);
 
     board = Board::default();
     board.new("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10");
-    perft::<PERFT_TYPE>(&mut board, 6);
+    perft::<PERFT_TYPE>(&mut board, 6, &reporter);
 }
 
 pub fn run_self_play() {
-    use std::sync::atomic::Ordering;
-
     let mut fen_file = File::open(
         "/home/harry-phillips/Desktop/antidraw_v2.1/AntiDraw_V2.1/UHO_2022/UHO_2022_+110_+119/UHO_2022_8mvs_+110_+119.epd",
     )
@@ -352,7 +266,13 @@ pub fn run_self_play() {
     let all_fens = string.split("\n").collect::<Vec<&str>>();
 
     for fen in all_fens.iter() {
-        let tt = Arc::new(Transposition::new(16));
+        let tt = Arc::new(Transposition::new(16, 1));
+        let thread_pool = ThreadPool::new(1);
+        let reporter: Arc<dyn SearchReporter> = Arc::new(UciReporter { show_wdl: false });
+        let time_control = TimeControl {
+            move_time: Some(1000),
+            ..TimeControl::default()
+        };
 
         let mut uci_moves_played: Vec<MovePly> = Vec::new();
 
@@ -365,32 +285,30 @@ pub fn run_self_play() {
             board.new(fen);
 
             for uci_move in &uci_moves_played {
-                board.make_move::<true>(uci_move);
+                board.play(uci_move);
             }
 
             // println!("{board}");
 
             let mut valid_moves = MoveList::default();
-            MoveGenerator::<GEN_ALL>::generate(&mut board, &mut |mut piece_moves| {
-                valid_moves.add_piece_moves(&mut piece_moves);
-            });
+            MoveGenerator::<GEN_ALL>::fill(&board, &mut valid_moves);
 
-            let match_result = Arbiter::arbitrate(&mut board, &mut valid_moves);
+            let match_result = Arbiter::arbitrate(&board, &valid_moves);
 
             match match_result {
                 MatchResult::Loss | MatchResult::Draw => break,
                 MatchResult::NoResult => {}
             }
 
-            let mut searcher = Searcher::new(&tt, &board, &SearchLimits::new(1000, 1000));
-
-            let move_played = searcher.iterative_deepening();
+            let move_played = thread_pool.search_start(
+                &tt,
+                &board,
+                &SearchLimits::new(TimeManager::new(&time_control), 1, Skill::default()),
+                &reporter,
+            );
 
             uci_moves_played.push(move_played);
             println!("{move_played}");
-
-            tt.curr_depth.store(0, Ordering::Relaxed);
-            tt.age();
         }
     }
 }
@@ -48,6 +48,19 @@ impl Zobrist {
         zobrist
     }
 
+    // identifies this exact set of keys, anything hashed with different keys is meaningless
+    pub fn fingerprint(&self) -> u64 {
+        let all_keys = self
+            .squares
+            .iter()
+            .flatten()
+            .chain(self.double_jump.iter())
+            .chain(self.castling_rights.iter())
+            .chain(std::iter::once(&self.side_to_move));
+
+        all_keys.fold(0, |fingerprint, key| fingerprint.rotate_left(7) ^ key)
+    }
+
     pub fn hash_from_board(&self, board: &Board) -> u64 {
         let mut final_zobrist: u64 = 0;
 
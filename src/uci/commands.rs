@@ -40,4 +40,6 @@ pub enum Commands {
 pub enum OptionsType {
     Spin { name: String, value: u16 },
     Button { name: String },
+    Check { name: String, value: bool },
+    String { name: String, value: String },
 }
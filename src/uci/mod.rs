@@ -1,3 +1,4 @@
 pub mod commands;
 pub mod option_table;
 pub mod parser;
+pub mod session;
@@ -1,8 +1,31 @@
+use crate::engine::skill::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO};
+
 // OPTION_NAME, MIN, MAX, DEFAULT
-pub const SPIN_OPTION_TABLE: &[(&str, u16, u16, u16)] =
-    &[("Hash", 1, 32768, 16), ("Threads", 1, 1024, 1)];
+pub const SPIN_OPTION_TABLE: &[(&str, u16, u16, u16)] = &[
+    ("Hash", 1, 32768, 16),
+    ("Threads", 1, 1024, 1),
+    ("Move Overhead", 0, 5000, 20),
+    ("MultiPV", 1, 256, 1),
+    (
+        "Skill Level",
+        0,
+        MAX_SKILL_LEVEL as u16,
+        MAX_SKILL_LEVEL as u16,
+    ),
+    ("UCI_Elo", MIN_ELO, MAX_ELO, MAX_ELO),
+];
+
+pub const BUTTON_OPTION_TABLE: &[&str] = &["Clear Hash", "Save Hash", "Load Hash"];
 
-pub const BUTTON_OPTION_TABLE: &[&str] = &["Clear Hash"];
+// OPTION_NAME, DEFAULT
+pub const CHECK_OPTION_TABLE: &[(&str, bool)] = &[
+    ("UCI_ShowWDL", false),
+    ("UCI_LimitStrength", false),
+    ("History Decay", true),
+];
+
+// OPTION_NAME, DEFAULT
+pub const STRING_OPTION_TABLE: &[(&str, &str)] = &[("Hash File", "hash.bin")];
 
 pub fn print_option_table() {
     for (name, min, max, default) in SPIN_OPTION_TABLE {
@@ -12,4 +35,12 @@ pub fn print_option_table() {
     for name in BUTTON_OPTION_TABLE {
         println!("option name {name} type button")
     }
+
+    for (name, default) in CHECK_OPTION_TABLE {
+        println!("option name {name} type check default {default}")
+    }
+
+    for (name, default) in STRING_OPTION_TABLE {
+        println!("option name {name} type string default {default}")
+    }
 }
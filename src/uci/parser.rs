@@ -1,5 +1,7 @@
 use crate::uci::commands::{Commands, OptionsType};
-use crate::uci::option_table::{BUTTON_OPTION_TABLE, SPIN_OPTION_TABLE};
+use crate::uci::option_table::{
+    BUTTON_OPTION_TABLE, CHECK_OPTION_TABLE, SPIN_OPTION_TABLE, STRING_OPTION_TABLE,
+};
 use std::str::FromStr;
 pub struct UCIParser {}
 use crate::engine::perft::{BULK_PERFT, PERFT, TT_PERFT};
@@ -10,6 +12,8 @@ const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0
 enum OptionClassifier {
     Spin,
     Button,
+    Check,
+    String,
     NoType,
 }
 
@@ -25,7 +29,7 @@ impl UCIParser {
             let section = (*split_messages.get(i).unwrap()).to_owned() + " ";
 
             if let Some(break_point) = break_point {
-                if section == break_point {
+                if section.trim_end() == break_point {
                     break;
                 }
             }
@@ -80,6 +84,18 @@ impl UCIParser {
             }
         }
 
+        for item in CHECK_OPTION_TABLE.iter() {
+            if option_name == item.0 {
+                option_type = OptionClassifier::Check
+            }
+        }
+
+        for item in STRING_OPTION_TABLE.iter() {
+            if option_name == item.0 {
+                option_type = OptionClassifier::String
+            }
+        }
+
         if option_type == OptionClassifier::NoType {
             println!("Command setoption of name '{option_name}' is not a valid option.");
             return Commands::IncorrectFormat;
@@ -116,6 +132,49 @@ impl UCIParser {
             };
         }
 
+        if option_type == OptionClassifier::Check {
+            let value = match split_message.iter().position(|&x| x == "value") {
+                Some(value_index) => split_message.get(value_index + 1).copied(),
+                None => None,
+            };
+
+            let value = match value {
+                Some("true") => true,
+                Some("false") => false,
+                _ => {
+                    println!(
+                        "Command setoption of option type check must have a value of true or false."
+                    );
+                    return Commands::IncorrectFormat;
+                }
+            };
+
+            return Commands::SetOption {
+                options_type: OptionsType::Check {
+                    name: option_name,
+                    value,
+                },
+            };
+        }
+
+        // string options are allowed to be empty and to contain spaces
+        if option_type == OptionClassifier::String {
+            let value = match split_message.iter().position(|&x| x == "value") {
+                Some(value_index) => {
+                    Self::collect_until_end_or_breakpoint(value_index + 1, &split_message, None)
+                        .unwrap_or_default()
+                }
+                None => String::new(),
+            };
+
+            return Commands::SetOption {
+                options_type: OptionsType::String {
+                    name: option_name,
+                    value,
+                },
+            };
+        }
+
         Commands::Unknown("The function failed".to_string())
     }
 
@@ -289,6 +348,7 @@ impl UCIParser {
         match initial_command {
             "uci" => Commands::Uci,
             "isready" => Commands::IsReady,
+            "ucinewgame" => Commands::UciNewGame,
             "quit" => Commands::Quit,
             "help" => Commands::Help,
             "setoption" => Self::parse_set_option(split_message),
@@ -0,0 +1,223 @@
+use crate::chess::board::Board;
+use crate::chess::move_ply;
+use crate::engine::perft::{BULK_PERFT, PERFT, TT_PERFT, perft};
+use crate::engine::reporter::{SearchReporter, UciReporter};
+use crate::engine::search_limits::SearchLimits;
+use crate::engine::skill::{self, Skill};
+use crate::engine::thread_pool::ThreadPool;
+use crate::engine::time_manager::{TimeControl, TimeManager};
+use crate::engine::transposition::Transposition;
+use crate::uci::commands::{Commands, OptionsType};
+use crate::uci::option_table::print_option_table;
+use crate::uci::parser;
+use std::sync::Arc;
+
+const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const HELP_MSG: &str = "\nA fairly generic rust engine supporting the UCI protocol.\n\
+Commands are the same as the uci protocol, except perft which can be called by perft <depth>\n\
+Some UCI features are yet to be implemented.\n\
+";
+
+// everything the gui has set up so far, options only take effect on the next go
+pub struct UciSession {
+    name: String,
+    author: String,
+    board: Board,
+    tt_size: u16,
+    num_threads: usize,
+    tt: Arc<Transposition>,
+    thread_pool: ThreadPool,
+    hash_file: String,
+    show_wdl: bool,
+    move_overhead: u32,
+    multi_pv: usize,
+    skill_level: u8,
+    limit_strength: bool,
+    elo: u16,
+    history_decay: bool,
+}
+
+impl UciSession {
+    pub fn new(name: &str, author: &str) -> Self {
+        let tt_size = 64;
+        let num_threads = 1;
+
+        Self {
+            name: name.to_string(),
+            author: author.to_string(),
+            board: Board::from_fen(START_POS),
+            tt_size,
+            num_threads,
+            tt: Arc::new(Transposition::new(tt_size, num_threads)),
+            thread_pool: ThreadPool::new(num_threads),
+            hash_file: String::from("hash.bin"),
+            show_wdl: false,
+            move_overhead: 20,
+            multi_pv: 1,
+            skill_level: skill::MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: skill::MAX_ELO,
+            history_decay: true,
+        }
+    }
+
+    // the first line has already been read to tell the protocols apart, so it is handled first
+    pub fn run(&mut self, first_input: &str) {
+        if !self.handle(parser::UCIParser::parse(first_input)) {
+            return;
+        }
+
+        loop {
+            let mut input = String::new();
+            let bytes_read = std::io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read input line.");
+
+            if bytes_read == 0 {
+                return;
+            }
+
+            if !self.handle(parser::UCIParser::parse(&input)) {
+                return;
+            }
+        }
+    }
+
+    // returns false once the gui wants the engine gone
+    fn handle(&mut self, command: Commands) -> bool {
+        match command {
+            Commands::Uci => {
+                println!("id name {}\nid author {}\n", self.name, self.author);
+                print_option_table();
+                println!("uciok");
+            }
+            Commands::IsReady => println!("readyok"),
+            Commands::Quit => return false,
+
+            Commands::Help => println!("{}", HELP_MSG),
+
+            Commands::UciNewGame => {
+                self.tt.clear(self.num_threads);
+                self.thread_pool.new_game();
+                self.board = Board::from_fen(START_POS);
+            }
+
+            Commands::Perft { depth } => {
+                let reporter = self.reporter();
+                perft::<PERFT>(&mut self.board, depth as u8, &reporter);
+            }
+
+            Commands::TTPerft { depth } => {
+                let reporter = self.reporter();
+                perft::<TT_PERFT>(&mut self.board, depth as u8, &reporter);
+            }
+
+            Commands::BulkPerft { depth } => {
+                let reporter = self.reporter();
+                perft::<BULK_PERFT>(&mut self.board, depth as u8, &reporter);
+            }
+            Commands::Position { fen, moves } => {
+                self.board = Board::from_fen(&fen);
+                if let Some(str_moves) = moves {
+                    for str_move in str_moves {
+                        self.board
+                            .play(&move_ply::uci_move_parser(&str_move, &self.board))
+                    }
+                }
+            }
+
+            Commands::Go {
+                move_time,
+                wtime,
+                btime,
+                winc,
+                binc,
+                moves_to_go,
+            } => {
+                let time_control = TimeControl {
+                    move_overhead: self.move_overhead,
+                    ..TimeControl::from_go(
+                        self.board.side_to_move(),
+                        move_time,
+                        wtime,
+                        btime,
+                        winc,
+                        binc,
+                        moves_to_go,
+                    )
+                };
+                let skill = Skill::new(self.limit_strength, self.elo, self.skill_level);
+                let search_limits =
+                    SearchLimits::new(TimeManager::new(&time_control), self.multi_pv, skill)
+                        .with_history_decay(self.history_decay);
+
+                let reporter: Arc<dyn SearchReporter> = Arc::new(self.reporter());
+
+                self.thread_pool
+                    .search_start(&self.tt, &self.board, &search_limits, &reporter);
+            }
+
+            Commands::SetOption { options_type } => self.set_option(options_type),
+
+            Commands::Unknown(line) if line != "\r\n" => {
+                println!("Unknown command: '{line}'. Type help for more information.\n")
+            }
+
+            _ => {}
+        }
+
+        true
+    }
+
+    fn reporter(&self) -> UciReporter {
+        UciReporter {
+            show_wdl: self.show_wdl,
+        }
+    }
+
+    fn set_option(&mut self, options_type: OptionsType) {
+        match options_type {
+            OptionsType::Spin { name, value } => match name.as_str() {
+                "Move Overhead" => self.move_overhead = value as u32,
+                "MultiPV" => self.multi_pv = value as usize,
+                "Skill Level" => self.skill_level = value as u8,
+                "UCI_Elo" => self.elo = value,
+                "Threads" => {
+                    self.num_threads = value as usize;
+                    self.thread_pool = ThreadPool::new(self.num_threads);
+                }
+                "Hash" => {
+                    self.tt_size = value;
+                    self.tt = Arc::new(Transposition::new(self.tt_size, self.num_threads));
+                }
+                _ => unreachable!(),
+            },
+
+            OptionsType::Button { name } => match name.as_str() {
+                "Clear Hash" => self.tt.clear(self.num_threads),
+                "Save Hash" => match self.tt.save(&self.hash_file) {
+                    Ok(()) => println!("info string saved hash to '{}'", self.hash_file),
+                    Err(error) => println!("info string failed to save hash: {error}"),
+                },
+                "Load Hash" => match self.tt.load(&self.hash_file) {
+                    Ok(()) => println!("info string loaded hash from '{}'", self.hash_file),
+                    Err(error) => println!("info string failed to load hash: {error}"),
+                },
+                _ => unreachable!(),
+            },
+
+            OptionsType::Check { name, value } => match name.as_str() {
+                "UCI_ShowWDL" => self.show_wdl = value,
+                "UCI_LimitStrength" => self.limit_strength = value,
+                "History Decay" => self.history_decay = value,
+                _ => unreachable!(),
+            },
+
+            OptionsType::String { name, value } => match name.as_str() {
+                "Hash File" => self.hash_file = value,
+                _ => unreachable!(),
+            },
+        }
+    }
+}
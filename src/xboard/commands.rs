@@ -0,0 +1,55 @@
+pub enum Commands {
+    Xboard,
+    Protover {
+        version: u32,
+    },
+    New,
+    SetBoard {
+        fen: String,
+    },
+    UserMove {
+        played: String,
+    },
+    Go,
+    PlayOther,
+    Force,
+    Level {
+        moves_per_session: u32,
+        base_time: u32,
+        increment: u32,
+    },
+    SetTime {
+        move_time: u32,
+    },
+    SetDepth {
+        depth: u8,
+    },
+    Time {
+        time_left: u32,
+    },
+    // the engine only budgets its own clock
+    OpponentTime,
+    Analyze,
+    ExitAnalyze,
+    Undo,
+    Remove,
+    Result,
+    Ping {
+        id: u32,
+    },
+    Post,
+    NoPost,
+    Memory {
+        size: u32,
+    },
+    Cores {
+        num_threads: u32,
+    },
+    MoveNow,
+    Quit,
+
+    // commands the engine accepts but has nothing to do for
+    Ignored,
+    Unknown(String),
+    IncorrectFormat(String),
+}
@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod parser;
+pub mod reporter;
+pub mod session;
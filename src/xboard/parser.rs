@@ -0,0 +1,226 @@
+use crate::xboard::commands::Commands;
+use std::str::FromStr;
+
+pub struct XBoardParser {}
+
+// every time is turned into milliseconds here so the session works in the same units as uci
+impl XBoardParser {
+    fn parse_number<T: FromStr>(split_message: &[&str], index: usize) -> Option<T> {
+        split_message
+            .get(index)
+            .and_then(|message| T::from_str(message).ok())
+    }
+
+    // the base time is either minutes or minutes:seconds
+    fn parse_base_time(message: &str) -> Option<u32> {
+        let (minutes, seconds) = match message.split_once(':') {
+            Some((minutes, seconds)) => {
+                (u32::from_str(minutes).ok()?, u32::from_str(seconds).ok()?)
+            }
+            None => (u32::from_str(message).ok()?, 0),
+        };
+
+        Some((minutes * 60 + seconds) * 1000)
+    }
+
+    // seconds, fractions are allowed for the increment and st
+    fn parse_seconds(message: &str) -> Option<u32> {
+        let seconds = f64::from_str(message).ok()?;
+        if seconds < 0.0 {
+            return None;
+        }
+
+        Some((seconds * 1000.0) as u32)
+    }
+
+    fn parse_level(split_message: &[&str]) -> Commands {
+        let moves_per_session = Self::parse_number::<u32>(split_message, 1);
+        let base_time = split_message
+            .get(2)
+            .and_then(|base| Self::parse_base_time(base));
+        let increment = split_message
+            .get(3)
+            .and_then(|inc| Self::parse_seconds(inc));
+
+        match (moves_per_session, base_time, increment) {
+            (Some(moves_per_session), Some(base_time), Some(increment)) => Commands::Level {
+                moves_per_session,
+                base_time,
+                increment,
+            },
+            _ => Commands::IncorrectFormat(String::from("level must be 'level MPS BASE INC'")),
+        }
+    }
+
+    fn parse_centiseconds(split_message: &[&str]) -> Option<u32> {
+        // some guis send negative times once the clock has run out
+        Self::parse_number::<i64>(split_message, 1).map(|time| (time.max(0) * 10) as u32)
+    }
+
+    pub fn parse(message: &str) -> Commands {
+        let split_message: Vec<_> = message.split_whitespace().collect();
+
+        let Some(&initial_command) = split_message.first() else {
+            return Commands::Ignored;
+        };
+
+        match initial_command {
+            "xboard" => Commands::Xboard,
+            "protover" => match Self::parse_number(&split_message, 1) {
+                Some(version) => Commands::Protover { version },
+                None => Commands::IncorrectFormat(String::from("protover needs a version")),
+            },
+            "new" => Commands::New,
+            "setboard" => match split_message.len() {
+                1 => Commands::IncorrectFormat(String::from("setboard needs a fen")),
+                _ => Commands::SetBoard {
+                    fen: split_message[1..].join(" "),
+                },
+            },
+            "usermove" => match split_message.get(1) {
+                Some(played) => Commands::UserMove {
+                    played: played.to_string(),
+                },
+                None => Commands::IncorrectFormat(String::from("usermove needs a move")),
+            },
+            "go" => Commands::Go,
+            "playother" => Commands::PlayOther,
+            "force" => Commands::Force,
+            "level" => Self::parse_level(&split_message),
+            "st" => match split_message
+                .get(1)
+                .and_then(|time| Self::parse_seconds(time))
+            {
+                Some(move_time) => Commands::SetTime { move_time },
+                None => Commands::IncorrectFormat(String::from("st needs a number of seconds")),
+            },
+            "sd" => match Self::parse_number(&split_message, 1) {
+                Some(depth) => Commands::SetDepth { depth },
+                None => Commands::IncorrectFormat(String::from("sd needs a depth")),
+            },
+            "time" => match Self::parse_centiseconds(&split_message) {
+                Some(time_left) => Commands::Time { time_left },
+                None => Commands::IncorrectFormat(String::from("time needs centiseconds")),
+            },
+            "otim" => match Self::parse_centiseconds(&split_message) {
+                Some(_) => Commands::OpponentTime,
+                None => Commands::IncorrectFormat(String::from("otim needs centiseconds")),
+            },
+            "analyze" => Commands::Analyze,
+            "exit" => Commands::ExitAnalyze,
+            "undo" => Commands::Undo,
+            "remove" => Commands::Remove,
+            "result" => Commands::Result,
+            "ping" => match Self::parse_number(&split_message, 1) {
+                Some(id) => Commands::Ping { id },
+                None => Commands::IncorrectFormat(String::from("ping needs a number")),
+            },
+            "post" => Commands::Post,
+            "nopost" => Commands::NoPost,
+            "memory" => match Self::parse_number(&split_message, 1) {
+                Some(size) => Commands::Memory { size },
+                None => Commands::IncorrectFormat(String::from("memory needs a size in MB")),
+            },
+            "cores" => match Self::parse_number(&split_message, 1) {
+                Some(num_threads) => Commands::Cores { num_threads },
+                None => Commands::IncorrectFormat(String::from("cores needs a number")),
+            },
+            "?" => Commands::MoveNow,
+            "quit" => Commands::Quit,
+
+            "accepted" | "rejected" | "random" | "easy" | "hard" | "computer" | "name"
+            | "rating" | "ics" | "variant" | "draw" | "hint" | "bk" | "." => Commands::Ignored,
+
+            _ => Commands::Unknown(String::from(initial_command)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert!(matches!(
+            XBoardParser::parse("level 40 5 0"),
+            Commands::Level {
+                moves_per_session: 40,
+                base_time: 300_000,
+                increment: 0,
+            }
+        ));
+        assert!(matches!(
+            XBoardParser::parse("level 0 2:30 1.5"),
+            Commands::Level {
+                moves_per_session: 0,
+                base_time: 150_000,
+                increment: 1500,
+            }
+        ));
+
+        for message in ["level", "level 40 5", "level 40 5:x 0", "level 40 5 -1"] {
+            assert!(matches!(
+                XBoardParser::parse(message),
+                Commands::IncorrectFormat(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_st() {
+        assert!(matches!(
+            XBoardParser::parse("st 30"),
+            Commands::SetTime { move_time: 30_000 }
+        ));
+        assert!(matches!(
+            XBoardParser::parse("st 0.5"),
+            Commands::SetTime { move_time: 500 }
+        ));
+
+        for message in ["st", "st -1", "st soon"] {
+            assert!(matches!(
+                XBoardParser::parse(message),
+                Commands::IncorrectFormat(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_time_and_otim() {
+        assert!(matches!(
+            XBoardParser::parse("time 12345"),
+            Commands::Time { time_left: 123_450 }
+        ));
+        assert!(matches!(
+            XBoardParser::parse("time -50"),
+            Commands::Time { time_left: 0 }
+        ));
+        assert!(matches!(
+            XBoardParser::parse("otim 6000"),
+            Commands::OpponentTime
+        ));
+
+        for message in ["time", "time 1.5", "otim", "otim lots"] {
+            assert!(matches!(
+                XBoardParser::parse(message),
+                Commands::IncorrectFormat(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_moves_and_move_now() {
+        assert!(matches!(
+            XBoardParser::parse("usermove e7e8q"),
+            Commands::UserMove { played } if played == "e7e8q"
+        ));
+        assert!(matches!(
+            XBoardParser::parse("usermove"),
+            Commands::IncorrectFormat(_)
+        ));
+        assert!(matches!(XBoardParser::parse("?"), Commands::MoveNow));
+        assert!(matches!(XBoardParser::parse("undo"), Commands::Undo));
+        assert!(matches!(XBoardParser::parse("remove"), Commands::Remove));
+    }
+}
@@ -0,0 +1,25 @@
+use crate::chess::board::Board;
+use crate::engine::info::IterationInfo;
+use crate::engine::reporter::{SearchReporter, pv_line};
+use crate::engine::score;
+
+// xboard thinking output is ply, score, time in centiseconds, nodes and the pv
+// it has no notion of bounds or multiple lines so only finished best lines are shown
+pub struct XBoardReporter;
+
+impl SearchReporter for XBoardReporter {
+    fn iteration(&self, _board: &Board, info: &IterationInfo) {
+        if info.pv_idx != 0 {
+            return;
+        }
+
+        let score = score::to_xboard(info.score);
+        let centiseconds = info.time / 10;
+        let pv_line = pv_line(info.pv);
+
+        println!(
+            "{} {score} {centiseconds} {} {pv_line}",
+            info.depth, info.nodes
+        );
+    }
+}
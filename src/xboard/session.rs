@@ -0,0 +1,492 @@
+use crate::chess::board::Board;
+use crate::chess::move_generator::{GEN_ALL, MoveGenerator};
+use crate::chess::move_list::MoveList;
+use crate::chess::move_ply;
+use crate::chess::types::color::Color;
+use crate::engine::arbiter::Arbiter;
+use crate::engine::reporter::{SearchReporter, SilentReporter};
+use crate::engine::search_limits::{MAX_SEARCH_DEPTH, SearchLimits};
+use crate::engine::skill::Skill;
+use crate::engine::thread_pool::ThreadPool;
+use crate::engine::time_manager::{TimeControl, TimeManager};
+use crate::engine::transposition::Transposition;
+use crate::engine::types::match_result::MatchResult;
+use crate::xboard::commands::Commands;
+use crate::xboard::parser::XBoardParser;
+use crate::xboard::reporter::XBoardReporter;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+const START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const MOVE_OVERHEAD: u32 = 20;
+
+// how often the input is checked for ? while the engine is thinking on a move
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// a cecp game, the engine plays engine_side whenever it is on the move and sits still in force mode
+pub struct XBoardSession {
+    name: String,
+    board: Board,
+    // undo can't go back further than the last new or setboard
+    moves_played: usize,
+    tt: Arc<Transposition>,
+    num_threads: usize,
+    thread_pool: ThreadPool,
+    engine_side: Option<Color>,
+    moves_per_session: u32,
+    base_time: Option<u32>,
+    increment: u32,
+    move_time: Option<u32>,
+    time_left: Option<u32>,
+    max_depth: u8,
+    post: bool,
+    analyzing: bool,
+    running_analysis: Option<SearchLimits>,
+    // stdin is read on its own thread so commands can arrive while the engine thinks
+    input: Option<Receiver<String>>,
+    // commands that came in while thinking, they are handled once the move is out
+    pending: VecDeque<String>,
+}
+
+impl XBoardSession {
+    pub fn new(name: &str, tt_size: u16, num_threads: usize) -> Self {
+        let mut board = Board::default();
+        board.new(START_POS);
+
+        Self {
+            name: name.to_string(),
+            board,
+            moves_played: 0,
+            tt: Arc::new(Transposition::new(tt_size, num_threads)),
+            num_threads,
+            thread_pool: ThreadPool::new(num_threads),
+            engine_side: Some(Color::Black),
+            moves_per_session: 0,
+            base_time: None,
+            increment: 0,
+            move_time: None,
+            time_left: None,
+            max_depth: MAX_SEARCH_DEPTH,
+            post: false,
+            analyzing: false,
+            running_analysis: None,
+            input: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn run(&mut self) {
+        self.input = Some(Self::spawn_input_reader());
+
+        while let Some(input) = self.next_line() {
+            if !self.handle(XBoardParser::parse(&input)) {
+                return;
+            }
+        }
+    }
+
+    fn spawn_input_reader() -> Receiver<String> {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            loop {
+                let mut input = String::new();
+                let bytes_read = std::io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input line.");
+
+                if bytes_read == 0 || sender.send(input).is_err() {
+                    return;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        if let Some(input) = self.pending.pop_front() {
+            return Some(input);
+        }
+
+        self.input.as_ref()?.recv().ok()
+    }
+
+    // returns false once the gui wants the engine gone
+    fn handle(&mut self, command: Commands) -> bool {
+        match command {
+            Commands::Xboard => {}
+            Commands::Protover { version } => {
+                if version >= 2 {
+                    self.print_features();
+                }
+            }
+
+            Commands::New => {
+                self.stop_analysis();
+                self.tt.clear(self.num_threads);
+                self.thread_pool.new_game();
+                self.set_position(START_POS);
+                self.engine_side = Some(Color::Black);
+                self.max_depth = MAX_SEARCH_DEPTH;
+                self.move_time = None;
+                self.start_analysis();
+            }
+
+            Commands::SetBoard { fen } => {
+                self.stop_analysis();
+                self.set_position(&fen);
+                self.start_analysis();
+            }
+
+            Commands::UserMove { played } => {
+                let Some(user_move) = move_ply::find_legal_move(&played, &self.board) else {
+                    println!("Illegal move: {played}");
+                    return true;
+                };
+
+                self.stop_analysis();
+                self.board.play(&user_move);
+                self.moves_played += 1;
+
+                if self.analyzing {
+                    self.start_analysis();
+                } else if self.engine_side == Some(self.board.side_to_move()) {
+                    self.think_and_move();
+                } else {
+                    self.print_result_if_over();
+                }
+            }
+
+            Commands::Go => {
+                self.engine_side = Some(self.board.side_to_move());
+                self.think_and_move();
+            }
+            Commands::PlayOther => self.engine_side = Some(!self.board.side_to_move()),
+            Commands::Force => self.engine_side = None,
+
+            Commands::Level {
+                moves_per_session,
+                base_time,
+                increment,
+            } => {
+                self.moves_per_session = moves_per_session;
+                self.base_time = Some(base_time);
+                self.increment = increment;
+                self.move_time = None;
+            }
+            Commands::SetTime { move_time } => self.move_time = Some(move_time),
+            Commands::SetDepth { depth } => self.max_depth = depth.clamp(1, MAX_SEARCH_DEPTH),
+            Commands::Time { time_left } => self.time_left = Some(time_left),
+            Commands::OpponentTime => {}
+
+            Commands::Analyze => {
+                self.analyzing = true;
+                self.engine_side = None;
+                self.start_analysis();
+            }
+            Commands::ExitAnalyze => {
+                self.stop_analysis();
+                self.analyzing = false;
+            }
+
+            Commands::Undo => self.take_back(1),
+            Commands::Remove => self.take_back(2),
+
+            Commands::Result => {
+                self.stop_analysis();
+                self.engine_side = None;
+            }
+
+            Commands::Ping { id } => println!("pong {id}"),
+            Commands::Post => self.post = true,
+            Commands::NoPost => self.post = false,
+
+            Commands::Memory { size } => {
+                self.tt = Arc::new(Transposition::new(
+                    size.clamp(1, u16::MAX as u32) as u16,
+                    self.num_threads,
+                ));
+            }
+            Commands::Cores { num_threads } => {
+                self.stop_analysis();
+                self.num_threads = num_threads.max(1) as usize;
+                self.thread_pool = ThreadPool::new(self.num_threads);
+                self.start_analysis();
+            }
+
+            // thinking watches the input for it, outside of that there is no search to hurry
+            Commands::MoveNow => {}
+
+            Commands::Quit => {
+                self.stop_analysis();
+                return false;
+            }
+
+            Commands::Ignored => {}
+            Commands::Unknown(command) => println!("Error (unknown command): {command}"),
+            Commands::IncorrectFormat(reason) => println!("Error (incorrect format): {reason}"),
+        }
+
+        true
+    }
+
+    fn print_features(&self) {
+        println!("feature done=0");
+        println!(
+            "feature myname=\"{}\" ping=1 setboard=1 usermove=1 playother=1 analyze=1 colors=0 sigint=0 sigterm=0 reuse=1 memory=1 smp=1 variants=\"normal\"",
+            self.name
+        );
+        println!("feature done=1");
+    }
+
+    // a fen that can't be set up leaves the game as it was
+    fn set_position(&mut self, fen: &str) {
+        match Board::try_from_fen(fen) {
+            Ok(board) => {
+                self.board = board;
+                self.moves_played = 0;
+            }
+            Err(_) => println!("Error (bad fen): {fen}"),
+        }
+    }
+
+    fn take_back(&mut self, num_moves: usize) {
+        if self.moves_played < num_moves {
+            println!("Error (no moves to take back): undo");
+            return;
+        }
+
+        self.stop_analysis();
+        for _ in 0..num_moves {
+            self.board.undo_move();
+        }
+        self.moves_played -= num_moves;
+        self.start_analysis();
+    }
+
+    fn game_result(&self) -> MatchResult {
+        let mut move_list = MoveList::default();
+        MoveGenerator::<GEN_ALL>::fill(&self.board, &mut move_list);
+
+        Arbiter::arbitrate(&self.board, &move_list)
+    }
+
+    // returns true when the game is already decided
+    fn print_result_if_over(&mut self) -> bool {
+        match self.game_result() {
+            MatchResult::Loss => match self.board.side_to_move() {
+                Color::White => println!("0-1 {{Black mates}}"),
+                Color::Black => println!("1-0 {{White mates}}"),
+            },
+            MatchResult::Draw => println!("1/2-1/2 {{Draw}}"),
+            MatchResult::NoResult => return false,
+        }
+
+        true
+    }
+
+    fn reporter(&self) -> Arc<dyn SearchReporter> {
+        if self.post || self.analyzing {
+            Arc::new(XBoardReporter)
+        } else {
+            Arc::new(SilentReporter)
+        }
+    }
+
+    // st wins over the clock, without either the time manager falls back to its default
+    fn time_control(&self) -> TimeControl {
+        if let Some(move_time) = self.move_time {
+            return TimeControl {
+                move_time: Some(move_time),
+                move_overhead: MOVE_OVERHEAD,
+                ..TimeControl::default()
+            };
+        }
+
+        // the session is counted from the last new or setboard, in full moves of the engine
+        let moves_to_go = if self.moves_per_session > 0 {
+            let moves_made = (self.moves_played / 2) as u32;
+            Some(self.moves_per_session - moves_made % self.moves_per_session)
+        } else {
+            None
+        };
+
+        TimeControl {
+            time_left: self.time_left.or(self.base_time),
+            increment: Some(self.increment),
+            moves_to_go,
+            move_time: None,
+            move_overhead: MOVE_OVERHEAD,
+        }
+    }
+
+    fn think_and_move(&mut self) {
+        if self.print_result_if_over() {
+            return;
+        }
+
+        let search_limits =
+            SearchLimits::new(TimeManager::new(&self.time_control()), 1, Skill::default())
+                .with_max_depth(self.max_depth);
+
+        self.thread_pool
+            .start(&self.tt, &self.board, &search_limits, &self.reporter());
+        self.watch_input_while_thinking();
+        let best_move = self.thread_pool.wait(&search_limits).best_move;
+
+        self.board.play(&best_move);
+        self.moves_played += 1;
+        println!("move {best_move}");
+
+        self.print_result_if_over();
+    }
+
+    // ? stops the search the same way uci stop does, everything else waits until the move is played
+    fn watch_input_while_thinking(&mut self) {
+        let Some(input) = &self.input else {
+            return;
+        };
+
+        while !self.thread_pool.is_stopped() {
+            match input.recv_timeout(INPUT_POLL_INTERVAL) {
+                Ok(line) => match XBoardParser::parse(&line) {
+                    Commands::MoveNow => self.thread_pool.stop(),
+                    Commands::Quit => {
+                        self.thread_pool.stop();
+                        self.pending.push_back(line);
+                    }
+                    _ => self.pending.push_back(line),
+                },
+                Err(RecvTimeoutError::Timeout) => {}
+                // nothing can arrive anymore, the search just runs out its time
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+
+    // analysis runs in the background while the loop keeps reading commands
+    fn start_analysis(&mut self) {
+        if !self.analyzing || !matches!(self.game_result(), MatchResult::NoResult) {
+            return;
+        }
+
+        let search_limits = SearchLimits::new(TimeManager::infinite(), 1, Skill::default())
+            .with_max_depth(self.max_depth);
+
+        self.thread_pool
+            .start(&self.tt, &self.board, &search_limits, &self.reporter());
+        self.running_analysis = Some(search_limits);
+    }
+
+    fn stop_analysis(&mut self) {
+        let Some(search_limits) = self.running_analysis.take() else {
+            return;
+        };
+
+        self.thread_pool.stop();
+        self.thread_pool.wait(&search_limits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn session() -> XBoardSession {
+        XBoardSession::new("test", 1, 1)
+    }
+
+    fn send(session: &mut XBoardSession, message: &str) {
+        assert!(session.handle(XBoardParser::parse(message)));
+    }
+
+    #[test]
+    fn test_bad_setboard_keeps_the_position() {
+        let mut session = session();
+        send(&mut session, "force");
+        send(&mut session, "usermove e2e4");
+
+        let before = session.board.to_fen(1);
+        for fen in [
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "rnbqkbnr/pppppppp w KQkq - 0 1",
+            "x",
+        ] {
+            send(&mut session, &format!("setboard {fen}"));
+            assert_eq!(session.board.to_fen(1), before);
+            assert_eq!(session.moves_played, 1);
+        }
+
+        send(&mut session, "setboard 4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(session.board.to_fen(1), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(session.moves_played, 0);
+    }
+
+    #[test]
+    fn test_usermove_plays_only_legal_moves() {
+        let mut session = session();
+        send(&mut session, "force");
+
+        send(&mut session, "usermove e2e5");
+        assert_eq!(session.board.to_fen(1), START_POS);
+        assert_eq!(session.moves_played, 0);
+
+        send(&mut session, "usermove e2e4");
+        send(&mut session, "usermove e7e5");
+        assert_eq!(
+            session.board.to_fen(2),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+        );
+        assert_eq!(session.moves_played, 2);
+    }
+
+    #[test]
+    fn test_undo_and_remove_take_moves_back() {
+        let mut session = session();
+        send(&mut session, "force");
+        for played in ["e2e4", "e7e5", "g1f3"] {
+            send(&mut session, &format!("usermove {played}"));
+        }
+
+        send(&mut session, "undo");
+        assert_eq!(session.moves_played, 2);
+        assert!(session.board.side_to_move() == Color::White);
+
+        send(&mut session, "remove");
+        assert_eq!(session.moves_played, 0);
+        assert_eq!(session.board.to_fen(1), START_POS);
+
+        // nothing to take back past the start of the game
+        send(&mut session, "undo");
+        assert_eq!(session.moves_played, 0);
+        assert_eq!(session.board.to_fen(1), START_POS);
+    }
+
+    #[test]
+    fn test_move_now_cuts_the_search_short() {
+        let mut session = session();
+        let (sender, receiver) = mpsc::channel();
+        session.input = Some(receiver);
+
+        send(&mut session, "st 30");
+        for message in ["ping 7", "?", "ping 8"] {
+            sender.send(String::from(message)).unwrap();
+        }
+
+        let timer = Instant::now();
+        send(&mut session, "go");
+
+        assert!(timer.elapsed() < Duration::from_secs(10));
+        assert_eq!(session.moves_played, 1);
+        assert!(session.board.side_to_move() == Color::Black);
+        // the commands around the ? are handled in order once the move is out
+        assert_eq!(session.next_line().as_deref(), Some("ping 7"));
+        assert_eq!(session.next_line().as_deref(), Some("ping 8"));
+    }
+}